@@ -0,0 +1,36 @@
+use day_3::generate::corrupted_memory;
+use day_3::instruction::InstructionSet;
+use day_3::machine::Machine;
+use day_3::parser::Parser;
+use day_3::scanner::Scanner;
+use std::hint::black_box;
+use std::time::Instant;
+
+const INPUT_LEN: usize = 64 * 1024 * 1024;
+
+fn main() {
+    let input = corrupted_memory(1, INPUT_LEN);
+    let instructions = InstructionSet::part_2();
+
+    let start = Instant::now();
+    let program = Parser::new(&instructions).parse(black_box(&input));
+    let parsed = Machine::new().run(&program, &instructions);
+    report("Parser", start);
+
+    let start = Instant::now();
+    let scanned = Scanner::new(black_box(input.as_bytes()), &instructions).run(&mut Machine::new());
+    report("Scanner", start);
+
+    assert_eq!(parsed, scanned);
+    println!("Result: {:?}", scanned);
+}
+
+fn report(name: &str, start: Instant) {
+    let elapsed = start.elapsed();
+    println!(
+        "{}: {:?} ({:.2} GB/s)",
+        name,
+        elapsed,
+        INPUT_LEN as f64 / elapsed.as_secs_f64() / 1e9
+    );
+}
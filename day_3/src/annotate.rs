@@ -0,0 +1,298 @@
+use crate::grammar::Grammar;
+use crate::instruction::InstructionSet;
+use crate::machine::Machine;
+use crate::parser::{Outcome, Parser};
+use std::ops::Range;
+
+/// How the parser and machine treated a stretch of corrupted memory.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Region {
+    Noise,
+    /// Looked like the start of a call but didn't parse.
+    Rejected,
+    /// A call that ran while the machine was enabled, or that enabled it.
+    Active(usize),
+    /// A call that ran while the machine was disabled and left it disabled, e.g. `mul` after `don't()`.
+    Suppressed(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    pub span: Range<usize>,
+    pub region: Region,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    Ansi,
+    Html,
+}
+
+/// Splits `input` into consecutive regions that cover every byte, in order.
+pub fn annotate(input: &str, instructions: &InstructionSet, grammar: Grammar) -> Vec<Annotation> {
+    let mut parser = Parser::with_grammar(instructions, grammar);
+    let mut machine = Machine::new();
+    let mut marked = Vec::new();
+
+    for outcome in parser.feed_outcomes(input.as_bytes()) {
+        match outcome {
+            Outcome::Accepted(call) => {
+                // An overflow leaves the machine as it was, which is all the view needs
+                let was_enabled = machine.enabled;
+                let _ = machine.execute(
+                    instructions,
+                    call.instruction,
+                    &call.args,
+                    call.span.clone(),
+                );
+
+                let region = if was_enabled || machine.enabled {
+                    Region::Active(call.instruction)
+                } else {
+                    Region::Suppressed(call.instruction)
+                };
+
+                marked.push(Annotation {
+                    span: call.span,
+                    region,
+                });
+            }
+            Outcome::Rejected(span) => marked.push(Annotation {
+                span,
+                region: Region::Rejected,
+            }),
+        }
+    }
+
+    if let Some(span) = parser.finish() {
+        marked.push(Annotation {
+            span,
+            region: Region::Rejected,
+        });
+    }
+
+    let mut annotations = Vec::with_capacity(marked.len() * 2 + 1);
+    let mut pos = 0;
+
+    for annotation in marked {
+        if annotation.span.start > pos {
+            annotations.push(Annotation {
+                span: pos..annotation.span.start,
+                region: Region::Noise,
+            });
+        }
+        pos = annotation.span.end;
+        annotations.push(annotation);
+    }
+
+    if pos < input.len() {
+        annotations.push(Annotation {
+            span: pos..input.len(),
+            region: Region::Noise,
+        });
+    }
+
+    annotations
+}
+
+const ANSI_COLORS: [&str; 6] = ["32", "36", "33", "35", "34", "92"];
+const HTML_COLORS: [&str; 6] = ["green", "teal", "darkgoldenrod", "purple", "blue", "olive"];
+
+/// Renders the annotated input followed by a legend with per-instruction counts.
+pub fn render(
+    input: &str,
+    annotations: &[Annotation],
+    instructions: &InstructionSet,
+    format: Format,
+) -> String {
+    let mut out = String::new();
+
+    if format == Format::Html {
+        out.push_str("<!DOCTYPE html>\n<html>\n<body>\n<pre>");
+    }
+
+    for annotation in annotations {
+        push_styled(
+            &mut out,
+            &input[annotation.span.clone()],
+            annotation.region,
+            format,
+        );
+    }
+
+    match format {
+        Format::Ansi => out.push_str("\n\nLegend:\n"),
+        Format::Html => out.push_str("</pre>\n<ul>\n"),
+    }
+
+    let count = |region: Region| annotations.iter().filter(|a| a.region == region).count();
+
+    let mut legend = Vec::new();
+    for index in 0..instructions.len() {
+        let name = instructions.get(index).name;
+        legend.push((format!("{} active", name), Region::Active(index)));
+        legend.push((format!("{} suppressed", name), Region::Suppressed(index)));
+    }
+    legend.push(("rejected".to_string(), Region::Rejected));
+
+    for (label, region) in legend {
+        if format == Format::Html {
+            out.push_str("<li>");
+        } else {
+            out.push_str("  ");
+        }
+        push_styled(&mut out, &label, region, format);
+        out.push_str(&format!(": {}", count(region)));
+        if format == Format::Html {
+            out.push_str("</li>");
+        }
+        out.push('\n');
+    }
+
+    if format == Format::Html {
+        out.push_str("</ul>\n</body>\n</html>\n");
+    }
+
+    out
+}
+
+fn push_styled(out: &mut String, text: &str, region: Region, format: Format) {
+    match format {
+        Format::Ansi => {
+            let style = match region {
+                Region::Noise => "2".to_string(),
+                Region::Rejected => "4;31".to_string(),
+                Region::Active(i) => format!("1;{}", ANSI_COLORS[i % ANSI_COLORS.len()]),
+                Region::Suppressed(i) => format!("2;9;{}", ANSI_COLORS[i % ANSI_COLORS.len()]),
+            };
+            out.push_str(&format!("\x1b[{}m{}\x1b[0m", style, text));
+        }
+        Format::Html => {
+            let style = match region {
+                Region::Noise => "color:gray".to_string(),
+                Region::Rejected => "color:red;text-decoration:underline wavy".to_string(),
+                Region::Active(i) => format!(
+                    "color:{};font-weight:bold",
+                    HTML_COLORS[i % HTML_COLORS.len()]
+                ),
+                Region::Suppressed(i) => format!(
+                    "color:{};opacity:0.5;text-decoration:line-through",
+                    HTML_COLORS[i % HTML_COLORS.len()]
+                ),
+            };
+            out.push_str(&format!("<span style=\"{}\">", style));
+            for c in text.chars() {
+                match c {
+                    '&' => out.push_str("&amp;"),
+                    '<' => out.push_str("&lt;"),
+                    '>' => out.push_str("&gt;"),
+                    '"' => out.push_str("&quot;"),
+                    _ => out.push(c),
+                }
+            }
+            out.push_str("</span>");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EXAMPLE: &str =
+        "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+
+    fn regions(input: &str) -> Vec<(&str, Region)> {
+        annotate(input, &InstructionSet::part_2(), Grammar::default())
+            .into_iter()
+            .map(|a| (&input[a.span], a.region))
+            .collect()
+    }
+
+    #[test]
+    fn test_annotate_example() {
+        use Region::*;
+
+        // mul is 0, do is 1 and don't is 2 in part 2
+        assert_eq!(
+            regions(EXAMPLE),
+            vec![
+                ("x", Noise),
+                ("mul(2,4)", Active(0)),
+                ("&mul[3,7]!^", Noise),
+                ("don't(", Active(2)),
+                (")_", Noise),
+                ("mul(5,5)", Suppressed(0)),
+                ("+", Noise),
+                ("mul(32,64", Rejected),
+                ("](", Noise),
+                ("mul(11,8)", Suppressed(0)),
+                ("un", Noise),
+                ("do(", Active(1)),
+                (")?", Noise),
+                ("mul(8,5)", Active(0)),
+                (")", Noise),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_annotate_rejected() {
+        use Region::*;
+
+        assert_eq!(
+            regions("mul(1234,5)mul(2,mul(1,2,3)mul(4,"),
+            vec![
+                ("mul(123", Rejected),
+                ("4,5)", Noise),
+                ("mul(2,", Rejected),
+                ("mul(1,2,3)", Rejected),
+                ("mul(4,", Rejected),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_annotations_cover_input() {
+        for seed in 0..50 {
+            let input = crate::generate::corrupted_memory(seed, 500);
+            let annotations = annotate(&input, &InstructionSet::part_2(), Grammar::default());
+
+            let mut pos = 0;
+            for annotation in &annotations {
+                assert_eq!(annotation.span.start, pos);
+                assert!(annotation.span.end > pos);
+                pos = annotation.span.end;
+            }
+            assert_eq!(pos, input.len());
+        }
+    }
+
+    #[test]
+    fn test_render_ansi() {
+        let instructions = InstructionSet::part_2();
+        let annotations = annotate(EXAMPLE, &instructions, Grammar::default());
+        let out = render(EXAMPLE, &annotations, &instructions, Format::Ansi);
+
+        assert!(out.contains("\x1b[1;32mmul(2,4)\x1b[0m"));
+        assert!(out.contains("\x1b[2;9;32mmul(5,5)\x1b[0m"));
+        assert!(out.contains("\x1b[1;33mdon't(\x1b[0m"));
+        assert!(out.contains("\x1b[4;31mmul(32,64\x1b[0m"));
+        assert!(out.contains("mul active\x1b[0m: 2"));
+        assert!(out.contains("mul suppressed\x1b[0m: 2"));
+        assert!(out.contains("rejected\x1b[0m: 1"));
+    }
+
+    #[test]
+    fn test_render_html_escapes() {
+        let input = "<b>&mul(2,4)\"";
+        let instructions = InstructionSet::part_2();
+        let annotations = annotate(input, &instructions, Grammar::default());
+        let out = render(input, &annotations, &instructions, Format::Html);
+
+        assert!(out.contains("&lt;b&gt;&amp;"));
+        assert!(out.contains("font-weight:bold\">mul(2,4)</span>"));
+        assert!(out.contains("&quot;"));
+        assert!(!out.contains("<b>"));
+    }
+}
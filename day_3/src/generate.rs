@@ -0,0 +1,64 @@
+use shared::rng::Rng;
+
+const NOISE: &[char] = &[
+    '!', '@', '#', '$', '%', '^', '&', '*', '(', ')', '[', ']', '{', '}', '<', '>', ',', ';', ':',
+    '\'', '+', '-', '_', ' ', '\n', '?', '/', 'a', 'd', 'm', 'u', 'l', 'o', 'n', 't', 'x', '0',
+    '1', '7', '9', 'é', '→',
+];
+
+const FRAGMENTS: &[&str] = &[
+    "mul(",
+    "mul(2,4)",
+    "mul(11,8)",
+    "mul(123,456)",
+    "mul(1234,5)",
+    "mul(0007,3)",
+    "mul(,4)",
+    "mul(4,)",
+    "mul(4,5",
+    "mul[3,7]",
+    "mul (2,4)",
+    "mul( 2,4)",
+    "mul(2,4,6)",
+    "mul(-2,4)",
+    "mul(+2,4)",
+    "mul( 2 , 4 )",
+    "mul(2 ,4)",
+    "mul(- 2,4)",
+    "mul(1 2,3)",
+    "mul(-999,+12)",
+    "mul(0,-0)",
+    "mul(99999999999,2)",
+    "do()",
+    "don't()",
+    "do(",
+    "don't(",
+    "do(1)",
+    "don't)",
+    "undo()",
+    "xdon't()",
+    "mulmul(3,3)",
+    "mul(3a,4)",
+    "mu",
+    "l(",
+    "(",
+    ")",
+    ",",
+];
+
+/// Deterministic generator of corrupted memory, for tests and benchmarks.
+/// Output mixes well-formed calls, near misses and random noise.
+pub fn corrupted_memory(seed: u64, len: usize) -> String {
+    let mut rng = Rng::new(seed);
+    let mut memory = String::with_capacity(len + 32);
+
+    while memory.len() < len {
+        if rng.below(3) == 0 {
+            memory.push_str(rng.pick(FRAGMENTS));
+        } else {
+            memory.push(rng.pick(NOISE));
+        }
+    }
+
+    memory
+}
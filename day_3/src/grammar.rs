@@ -0,0 +1,37 @@
+/// Rules for what counts as an operand inside an argument list.
+///
+/// The puzzle only allows `mul(X,Y)` where X and Y are 1-3 digit numbers, which is the default.
+/// Whitespace, when allowed, may only appear around operands inside the parentheses,
+/// e.g. `mul( 2 , 4 )`. A sign must directly precede the digits it applies to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Grammar {
+    /// Maximum digits per operand, leading zeros included. `None` for no limit.
+    pub max_digits: Option<usize>,
+    pub allow_whitespace: bool,
+    pub allow_signs: bool,
+}
+
+impl Default for Grammar {
+    fn default() -> Self {
+        Self {
+            max_digits: Some(3),
+            allow_whitespace: false,
+            allow_signs: false,
+        }
+    }
+}
+
+impl Grammar {
+    /// Accepts anything that could reasonably be read as a call.
+    pub fn lenient() -> Self {
+        Self {
+            max_digits: None,
+            allow_whitespace: true,
+            allow_signs: true,
+        }
+    }
+
+    pub fn digits_allowed(&self, digits: usize) -> bool {
+        self.max_digits.is_none_or(|max| digits <= max)
+    }
+}
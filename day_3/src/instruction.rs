@@ -0,0 +1,188 @@
+use crate::machine::{Machine, Overflow};
+use std::ops::Range;
+
+/// Upper bound on the operands of any call, so scanners can keep them in a fixed buffer.
+pub const MAX_ARGS: usize = 16;
+
+/// How many operands an instruction accepts between its parentheses.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Arity {
+    Exact(usize),
+    Variadic { min: usize },
+}
+
+impl Arity {
+    pub fn accepts(&self, count: usize) -> bool {
+        match self {
+            Arity::Exact(n) => count == *n,
+            Arity::Variadic { min } => count >= *min && count <= MAX_ARGS,
+        }
+    }
+}
+
+/// What an instruction does to the machine when it is executed.
+/// Arithmetic should be checked and report `Overflow` rather than wrap.
+pub type Semantics = fn(&mut Machine, &[i64]) -> Result<(), Overflow>;
+
+pub struct Instruction {
+    pub name: &'static str,
+    pub arity: Arity,
+    pub semantics: Semantics,
+}
+
+/// A single instruction recovered from corrupted memory.
+/// `instruction` is the index of the instruction in its `InstructionSet`,
+/// `span` the bytes from the start of its name up to and including the `)`, or the `(` for
+/// instructions without operands, which take effect there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Call {
+    pub instruction: usize,
+    pub args: Vec<i64>,
+    pub span: Range<usize>,
+}
+
+#[derive(Default)]
+pub struct InstructionSet {
+    instructions: Vec<Instruction>,
+
+    // Indices into `instructions`, longest name first
+    by_len: Vec<usize>,
+}
+
+impl InstructionSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only `mul(X,Y)`.
+    pub fn part_1() -> Self {
+        let mut set = Self::new();
+        set.register("mul", Arity::Exact(2), mul);
+        set
+    }
+
+    /// `mul(X,Y)` plus the `do()` and `don't()` toggles.
+    pub fn part_2() -> Self {
+        let mut set = Self::part_1();
+        set.register("do", Arity::Exact(0), |machine, _| {
+            machine.enabled = true;
+            Ok(())
+        });
+        set.register("don't", Arity::Exact(0), |machine, _| {
+            machine.enabled = false;
+            Ok(())
+        });
+        set
+    }
+
+    /// Adds an instruction to the set and returns its index.
+    /// Registering a name twice replaces the earlier instruction.
+    ///
+    /// Names must be non-empty and made of ASCII letters and `'`, the characters the parsers treat as part of a name.
+    pub fn register(&mut self, name: &'static str, arity: Arity, semantics: Semantics) -> usize {
+        assert!(
+            !name.is_empty() && name.bytes().all(|b| b.is_ascii_alphabetic() || b == b'\''),
+            "Invalid instruction name: {:?}",
+            name
+        );
+        assert!(
+            !matches!(arity, Arity::Exact(n) | Arity::Variadic { min: n } if n > MAX_ARGS),
+            "Arity of {} is above MAX_ARGS",
+            name
+        );
+
+        let instruction = Instruction {
+            name,
+            arity,
+            semantics,
+        };
+
+        if let Some(index) = self.instructions.iter().position(|i| i.name == name) {
+            self.instructions[index] = instruction;
+            return index;
+        }
+
+        self.instructions.push(instruction);
+
+        let index = self.instructions.len() - 1;
+        self.by_len.push(index);
+        self.by_len
+            .sort_by_key(|i| std::cmp::Reverse(self.instructions[*i].name.len()));
+
+        index
+    }
+
+    pub fn get(&self, index: usize) -> &Instruction {
+        &self.instructions[index]
+    }
+
+    pub fn len(&self) -> usize {
+        self.instructions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instructions.is_empty()
+    }
+
+    /// Finds the instruction whose name is the longest suffix of `name`.
+    /// Corrupted memory has no separators, so `xdon't` must resolve to `don't` and not `t`.
+    pub fn resolve(&self, name: &[u8]) -> Option<usize> {
+        self.by_len
+            .iter()
+            .copied()
+            .find(|i| name.ends_with(self.instructions[*i].name.as_bytes()))
+    }
+
+    /// Length of the longest registered name, i.e. how far back a scanner has to look from a `(`.
+    pub fn max_name_len(&self) -> usize {
+        self.by_len
+            .first()
+            .map_or(0, |i| self.instructions[*i].name.len())
+    }
+}
+
+fn mul(machine: &mut Machine, args: &[i64]) -> Result<(), Overflow> {
+    if machine.enabled {
+        machine.add(args[0].checked_mul(args[1]).ok_or(Overflow)?)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_arity() {
+        assert!(Arity::Exact(2).accepts(2));
+        assert!(!Arity::Exact(2).accepts(1));
+        assert!(Arity::Variadic { min: 1 }.accepts(5));
+        assert!(!Arity::Variadic { min: 1 }.accepts(0));
+        assert!(!Arity::Variadic { min: 1 }.accepts(MAX_ARGS + 1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_register_rejects_non_letter_names() {
+        InstructionSet::new().register("mul2", Arity::Exact(2), |_, _| Ok(()));
+    }
+
+    #[test]
+    fn test_resolve_longest_suffix() {
+        let set = InstructionSet::part_2();
+        assert_eq!(set.get(set.resolve(b"xdon't").unwrap()).name, "don't");
+        assert_eq!(set.get(set.resolve(b"undo").unwrap()).name, "do");
+        assert_eq!(set.get(set.resolve(b"mul").unwrap()).name, "mul");
+        assert_eq!(set.resolve(b"mu"), None);
+        assert_eq!(set.resolve(b""), None);
+        assert_eq!(set.max_name_len(), 5);
+    }
+
+    #[test]
+    fn test_register_replaces() {
+        let mut set = InstructionSet::part_1();
+        let index = set.register("mul", Arity::Exact(3), |_, _| Ok(()));
+        assert_eq!(index, 0);
+        assert_eq!(set.get(0).arity, Arity::Exact(3));
+    }
+}
@@ -0,0 +1,9 @@
+pub mod annotate;
+pub mod generate;
+pub mod grammar;
+pub mod instruction;
+pub mod machine;
+pub mod parser;
+#[cfg(feature = "regex")]
+pub mod regex_parser;
+pub mod scanner;
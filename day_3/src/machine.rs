@@ -0,0 +1,151 @@
+use crate::instruction::{Call, InstructionSet};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+
+/// Returned by instruction semantics when a result doesn't fit in an `i64`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Overflow;
+
+/// An `Overflow` tied to the call that caused it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverflowError {
+    pub instruction: &'static str,
+    pub span: Range<usize>,
+}
+
+impl fmt::Display for OverflowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} overflowed the accumulator at bytes {}..{}",
+            self.instruction, self.span.start, self.span.end
+        )
+    }
+}
+
+/// State that recovered instructions operate on.
+/// `enabled` is the `do()`/`don't()` switch; `flags` holds any other switches custom instructions need.
+pub struct Machine {
+    pub enabled: bool,
+    pub acc: i64,
+    pub flags: BTreeMap<&'static str, bool>,
+}
+
+impl Default for Machine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Machine {
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            acc: 0,
+            flags: BTreeMap::new(),
+        }
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.get(name).copied().unwrap_or(false)
+    }
+
+    pub fn set_flag(&mut self, name: &'static str, value: bool) {
+        self.flags.insert(name, value);
+    }
+
+    /// Adds `value` to the accumulator, leaving it untouched on overflow.
+    pub fn add(&mut self, value: i64) -> Result<(), Overflow> {
+        self.acc = self.acc.checked_add(value).ok_or(Overflow)?;
+        Ok(())
+    }
+
+    /// Executes `program` in order and returns the accumulator.
+    /// Stops at the first call that overflows.
+    pub fn run(
+        &mut self,
+        program: &[Call],
+        instructions: &InstructionSet,
+    ) -> Result<i64, OverflowError> {
+        for call in program {
+            self.execute(
+                instructions,
+                call.instruction,
+                &call.args,
+                call.span.clone(),
+            )?;
+        }
+
+        Ok(self.acc)
+    }
+
+    pub fn execute(
+        &mut self,
+        instructions: &InstructionSet,
+        instruction: usize,
+        args: &[i64],
+        span: Range<usize>,
+    ) -> Result<(), OverflowError> {
+        let instruction = instructions.get(instruction);
+
+        (instruction.semantics)(self, args).map_err(|_| OverflowError {
+            instruction: instruction.name,
+            span,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::grammar::Grammar;
+    use crate::parser::Parser;
+
+    fn eval(input: &str) -> Result<i64, OverflowError> {
+        let instructions = InstructionSet::part_2();
+        let program = Parser::with_grammar(&instructions, Grammar::lenient()).parse(input);
+        Machine::new().run(&program, &instructions)
+    }
+
+    #[test]
+    fn test_big_operands() {
+        assert_eq!(eval("mul(2147483648,2)"), Ok(4294967296));
+        assert_eq!(eval("mul(-9223372036854775808,1)"), Ok(i64::MIN));
+        assert_eq!(eval("mul(9223372036854775807,1)"), Ok(i64::MAX));
+    }
+
+    #[test]
+    fn test_product_overflow() {
+        assert_eq!(
+            eval("mul(2,3)x mul(9999999999,9999999999)mul(1,1)"),
+            Err(OverflowError {
+                instruction: "mul",
+                span: 10..36,
+            })
+        );
+        assert!(eval("mul(-9223372036854775808,-1)").is_err());
+    }
+
+    #[test]
+    fn test_accumulator_overflow() {
+        let input = "mul(3037000499,3037000499)_mul(3037000499,3037000499)";
+        let err = eval(input).unwrap_err();
+        assert_eq!(&input[err.span.clone()], "mul(3037000499,3037000499)");
+        assert_eq!(err.span, 27..53);
+        assert_eq!(
+            err.to_string(),
+            "mul overflowed the accumulator at bytes 27..53"
+        );
+    }
+
+    #[test]
+    fn test_overflow_while_disabled_is_ignored() {
+        assert_eq!(eval("don't()mul(9999999999,9999999999)do()mul(2,3)"), Ok(6));
+    }
+
+    #[test]
+    fn test_operand_too_big_is_not_a_call() {
+        assert_eq!(eval("mul(9223372036854775808,1)mul(2,3)"), Ok(6));
+    }
+}
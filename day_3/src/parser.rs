@@ -0,0 +1,475 @@
+use crate::grammar::Grammar;
+use crate::instruction::{Arity, Call, InstructionSet, MAX_ARGS};
+use std::ops::Range;
+
+// Only the last `limit` bytes of a name can ever match, so that's all that is kept
+struct CommandStack {
+    chars: Vec<u8>,
+    limit: usize,
+}
+
+impl CommandStack {
+    fn new(limit: usize) -> Self {
+        Self {
+            chars: Vec::with_capacity(limit),
+            limit,
+        }
+    }
+
+    fn push(&mut self, c: u8) {
+        self.chars.push(c);
+        if self.chars.len() > self.limit {
+            self.chars.remove(0);
+        }
+    }
+
+    fn resolve_command(&self, instructions: &InstructionSet) -> Option<usize> {
+        instructions.resolve(&self.chars)
+    }
+
+    fn clear(&mut self) {
+        self.chars.clear()
+    }
+}
+
+#[derive(Default)]
+struct NumStack {
+    chars: Vec<u8>,
+}
+
+impl NumStack {
+    fn push(&mut self, c: u8) {
+        self.chars.push(c);
+    }
+
+    fn resolve_number(&self) -> Option<i64> {
+        std::str::from_utf8(&self.chars).ok()?.parse::<i64>().ok()
+    }
+
+    fn digits(&self) -> usize {
+        self.chars.iter().filter(|c| c.is_ascii_digit()).count()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+
+    fn clear(&mut self) {
+        self.chars.clear()
+    }
+}
+
+/// What the parser concluded once it left an argument list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Accepted(Call),
+    /// A known instruction name and `(` that didn't turn into a valid call.
+    /// The span runs from the name to the byte that broke the call, including it if it was consumed.
+    Rejected(Range<usize>),
+}
+
+/// Recovers instruction calls of the form `name(a,b,...)` from corrupted memory.
+/// Anything that doesn't fit that shape is skipped.
+///
+/// Input can be given all at once with `parse`, or in chunks of any size with `feed` followed by
+/// `finish`. State carries over between chunks, so calls split across a boundary are still found.
+pub struct Parser<'a> {
+    instructions: &'a InstructionSet,
+    grammar: Grammar,
+
+    // Offset of the next byte from the start of the stream
+    pos: usize,
+
+    // The instruction whose argument list is being read, if any, and where its name started
+    call: Option<usize>,
+    call_start: usize,
+
+    cmd_stack: CommandStack,
+    num_stack: NumStack,
+
+    args: Vec<i64>,
+
+    // Whitespace followed the current operand, so it can't take more digits
+    num_closed: bool,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(instructions: &'a InstructionSet) -> Self {
+        Self::with_grammar(instructions, Grammar::default())
+    }
+
+    pub fn with_grammar(instructions: &'a InstructionSet, grammar: Grammar) -> Self {
+        Self {
+            instructions,
+            grammar,
+            pos: 0,
+            call: None,
+            call_start: 0,
+            cmd_stack: CommandStack::new(instructions.max_name_len()),
+            num_stack: NumStack::default(),
+            args: Vec::new(),
+            num_closed: false,
+        }
+    }
+
+    pub fn parse(&mut self, input: &str) -> Vec<Call> {
+        let program = self.feed(input.as_bytes());
+        self.finish();
+        program
+    }
+
+    /// Parses the next chunk of input and returns the calls completed within it.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<Call> {
+        self.feed_outcomes(chunk)
+            .into_iter()
+            .filter_map(|outcome| match outcome {
+                Outcome::Accepted(call) => Some(call),
+                Outcome::Rejected(_) => None,
+            })
+            .collect()
+    }
+
+    /// Like `feed`, but also reports the attempted calls that were rejected.
+    pub fn feed_outcomes(&mut self, chunk: &[u8]) -> Vec<Outcome> {
+        let mut outcomes = Vec::new();
+
+        for c in chunk {
+            if let Some(outcome) = self.step(*c) {
+                outcomes.push(outcome);
+            }
+            self.pos += 1;
+        }
+
+        outcomes
+    }
+
+    /// Ends the input and gets the parser ready for a new stream.
+    /// A call still missing its `)` is dropped; its span is returned.
+    pub fn finish(&mut self) -> Option<Range<usize>> {
+        let unterminated = self.call.map(|_| self.call_start..self.pos);
+        self.reset();
+        self.pos = 0;
+        unterminated
+    }
+
+    // Names are ASCII, so bytes of multi-byte characters can be treated like any other noise
+    fn step(&mut self, c: u8) -> Option<Outcome> {
+        let Some(instruction) = self.call else {
+            if c == b'(' {
+                let resolved = self.cmd_stack.resolve_command(self.instructions);
+                self.cmd_stack.clear();
+
+                if let Some(instruction) = resolved {
+                    self.call_start = self.pos - self.instructions.get(instruction).name.len();
+
+                    // Instructions without operands, like the toggles, take effect at the `(`
+                    if self.instructions.get(instruction).arity == Arity::Exact(0) {
+                        return Some(Outcome::Accepted(Call {
+                            instruction,
+                            args: Vec::new(),
+                            span: self.call_start..self.pos + 1,
+                        }));
+                    }
+                }
+                self.call = resolved;
+            } else if c.is_ascii_alphabetic() || c == b'\'' {
+                self.cmd_stack.push(c);
+            } else {
+                self.reset();
+            }
+            return None;
+        };
+
+        if c == b')' {
+            let outcome = match self.finish_call(instruction) {
+                Some(call) => Outcome::Accepted(call),
+                None => Outcome::Rejected(self.call_start..self.pos + 1),
+            };
+            self.reset();
+            Some(outcome)
+        } else if c == b',' {
+            match self.num_stack.resolve_number() {
+                Some(n) if self.args.len() + 1 < MAX_ARGS => {
+                    self.args.push(n);
+                    self.num_stack.clear();
+                    self.num_closed = false;
+                    None
+                }
+                _ => {
+                    self.reset();
+                    Some(Outcome::Rejected(self.call_start..self.pos + 1))
+                }
+            }
+        } else if self.extends_operand(c) {
+            self.num_stack.push(c);
+            None
+        } else if c.is_ascii_whitespace()
+            && self.grammar.allow_whitespace
+            && (self.num_stack.is_empty() || self.num_stack.digits() > 0)
+        {
+            self.num_closed = !self.num_stack.is_empty();
+            None
+        } else {
+            // Not part of this call, but it may start the next one
+            let rejected = self.call_start..self.pos;
+            self.reset();
+            self.step(c);
+            Some(Outcome::Rejected(rejected))
+        }
+    }
+
+    // Whether `c` is a digit or sign that can be added to the operand being read
+    fn extends_operand(&self, c: u8) -> bool {
+        if c.is_ascii_digit() {
+            !self.num_closed && self.grammar.digits_allowed(self.num_stack.digits() + 1)
+        } else {
+            (c == b'+' || c == b'-') && self.grammar.allow_signs && self.num_stack.is_empty()
+        }
+    }
+
+    fn finish_call(&mut self, instruction: usize) -> Option<Call> {
+        if !self.num_stack.is_empty() || !self.args.is_empty() {
+            self.args.push(self.num_stack.resolve_number()?);
+        }
+
+        let args = std::mem::take(&mut self.args);
+
+        self.instructions
+            .get(instruction)
+            .arity
+            .accepts(args.len())
+            .then_some(Call {
+                instruction,
+                args,
+                span: self.call_start..self.pos + 1,
+            })
+    }
+
+    fn reset(&mut self) {
+        self.num_stack.clear();
+        self.cmd_stack.clear();
+        self.call = None;
+        self.args.clear();
+        self.num_closed = false;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generate::corrupted_memory;
+    use crate::machine::Machine;
+    use shared::rng::Rng;
+
+    fn eval(input: &str, instructions: &InstructionSet) -> i64 {
+        let program = Parser::new(instructions).parse(input);
+        Machine::new().run(&program, instructions).unwrap()
+    }
+
+    fn eval_with(input: &str, grammar: Grammar) -> i64 {
+        let instructions = InstructionSet::part_1();
+        let program = Parser::with_grammar(&instructions, grammar).parse(input);
+        Machine::new().run(&program, &instructions).unwrap()
+    }
+
+    #[test]
+    fn test_part_1_example() {
+        let input = "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))";
+        assert_eq!(eval(input, &InstructionSet::part_1()), 161);
+    }
+
+    #[test]
+    fn test_part_2_example() {
+        let input = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        assert_eq!(eval(input, &InstructionSet::part_2()), 48);
+    }
+
+    #[test]
+    fn test_rejects_malformed_calls() {
+        let set = InstructionSet::part_1();
+        assert_eq!(eval("mul(3a,4)", &set), 0);
+        assert_eq!(eval("2mul(3,4)", &set), 12);
+        assert_eq!(eval("mul(,4)", &set), 0);
+        assert_eq!(eval("mul(4,)", &set), 0);
+        assert_eq!(eval("mul(1,2,3)", &set), 0);
+        assert_eq!(eval("mul(2,mul(3,4)", &set), 12);
+    }
+
+    #[test]
+    fn test_toggles_take_effect_at_open_paren() {
+        let set = InstructionSet::part_2();
+        assert_eq!(eval("don't(mul(2,3)", &set), 0);
+        assert_eq!(eval("don't()do(mul(2,3)", &set), 6);
+    }
+
+    #[test]
+    fn test_max_digits() {
+        let set = InstructionSet::part_1();
+        assert_eq!(eval("mul(1,2)", &set), 2);
+        assert_eq!(eval("mul(999,999)", &set), 998001);
+        assert_eq!(eval("mul(007,5)", &set), 35);
+        assert_eq!(eval("mul(1234,5)", &set), 0);
+        assert_eq!(eval("mul(5,1234)", &set), 0);
+        assert_eq!(eval("mul(0001,5)", &set), 0);
+        assert_eq!(eval("mul(1234,5)mul(2,3)", &set), 6);
+
+        let grammar = Grammar {
+            max_digits: Some(4),
+            ..Grammar::default()
+        };
+        assert_eq!(eval_with("mul(1234,5)", grammar), 6170);
+        assert_eq!(eval_with("mul(12345,5)", grammar), 0);
+
+        let grammar = Grammar {
+            max_digits: None,
+            ..Grammar::default()
+        };
+        assert_eq!(eval_with("mul(0000000000000012,5)", grammar), 60);
+        assert_eq!(eval_with("mul(2147483648,1)", grammar), 2147483648);
+        assert_eq!(eval_with("mul(9223372036854775807,1)", grammar), i64::MAX);
+        assert_eq!(eval_with("mul(9223372036854775808,1)", grammar), 0);
+    }
+
+    #[test]
+    fn test_whitespace() {
+        let strict = Grammar::default();
+        let grammar = Grammar {
+            allow_whitespace: true,
+            ..Grammar::default()
+        };
+
+        assert_eq!(eval_with("mul( 2,4)", strict), 0);
+        assert_eq!(eval_with("mul( 2,4)", grammar), 8);
+        assert_eq!(eval_with("mul(2 , 4)", grammar), 8);
+        assert_eq!(eval_with("mul(\t2,\n4 )", grammar), 8);
+        assert_eq!(eval_with("mul(1 2,4)", grammar), 0);
+        assert_eq!(eval_with("mul (2,4)", grammar), 0);
+    }
+
+    #[test]
+    fn test_signs() {
+        let strict = Grammar::default();
+        let grammar = Grammar {
+            allow_signs: true,
+            ..Grammar::default()
+        };
+
+        assert_eq!(eval_with("mul(-2,4)", strict), 0);
+        assert_eq!(eval_with("mul(-2,4)", grammar), -8);
+        assert_eq!(eval_with("mul(+2,-4)", grammar), -8);
+        assert_eq!(eval_with("mul(-999,1)", grammar), -999);
+        assert_eq!(eval_with("mul(-1000,1)", grammar), 0);
+        assert_eq!(eval_with("mul(--2,4)", grammar), 0);
+        assert_eq!(eval_with("mul(2-,4)", grammar), 0);
+        assert_eq!(eval_with("mul(-,4)", grammar), 0);
+        assert_eq!(eval_with("mul(- 2,4)", Grammar::lenient()), 0);
+        assert_eq!(eval_with("mul( -2 , -4 )", Grammar::lenient()), 8);
+    }
+
+    #[test]
+    fn test_custom_instructions() {
+        let mut set = InstructionSet::part_2();
+        set.register("add", Arity::Exact(2), |machine, args| {
+            machine.add(args[0] + args[1])
+        });
+        set.register("sum", Arity::Variadic { min: 1 }, |machine, args| {
+            machine.add(args.iter().sum())
+        });
+        set.register("neg", Arity::Exact(0), |machine, _| {
+            let negate = !machine.flag("neg");
+            machine.set_flag("neg", negate);
+            Ok(())
+        });
+        set.register("sub", Arity::Exact(2), |machine, args| {
+            if machine.flag("neg") {
+                machine.add(args[1] - args[0])
+            } else {
+                machine.add(args[0] - args[1])
+            }
+        });
+
+        let program = Parser::new(&set).parse("add(1,2)sum(1,2,3)sum()neg()sub(5,3)mul(2,2)");
+        assert_eq!(program.len(), 5);
+
+        let mut machine = Machine::new();
+        assert_eq!(machine.run(&program, &set), Ok(3 + 6 - 2 + 4));
+        assert!(machine.flag("neg"));
+    }
+
+    fn feed_in_chunks(parser: &mut Parser, input: &[u8], rng: &mut Rng) -> Vec<Call> {
+        let mut program = Vec::new();
+        let mut rest = input;
+
+        while !rest.is_empty() {
+            let (chunk, tail) = rest.split_at(1 + rng.below(rest.len().min(64)));
+            program.extend(parser.feed(chunk));
+            rest = tail;
+        }
+
+        parser.finish();
+        program
+    }
+
+    #[test]
+    fn test_call_split_across_chunks() {
+        let set = InstructionSet::part_2();
+        let mut parser = Parser::new(&set);
+
+        let mut program = parser.feed(b"xmu");
+        program.extend(parser.feed(b"l(12"));
+        program.extend(parser.feed(b"3,4"));
+        program.extend(parser.feed(b")don"));
+        program.extend(parser.feed(b"'t()mul(1,"));
+        parser.finish();
+
+        assert_eq!(program.len(), 2);
+        assert_eq!(program[0].args, vec![123, 4]);
+        assert_eq!(set.get(program[1].instruction).name, "don't");
+
+        // `finish` drops the unterminated `mul(1,` instead of carrying it into the next stream
+        assert!(parser.feed(b"2)").is_empty());
+    }
+
+    #[test]
+    fn test_multi_byte_char_split_across_chunks() {
+        let set = InstructionSet::part_1();
+        let input = "éémul(2,3)→mul(4,5)".as_bytes();
+
+        for split in 0..input.len() {
+            let mut parser = Parser::new(&set);
+            let mut program = parser.feed(&input[..split]);
+            program.extend(parser.feed(&input[split..]));
+            parser.finish();
+            assert_eq!(program.len(), 2, "split at {}", split);
+        }
+    }
+
+    #[test]
+    fn test_chunking_never_changes_result() {
+        let mut rng = Rng::new(30);
+
+        for seed in 0..200 {
+            let input = corrupted_memory(seed, 1_000);
+
+            for grammar in [Grammar::default(), Grammar::lenient()] {
+                let set = InstructionSet::part_2();
+                let whole = Parser::with_grammar(&set, grammar).parse(&input);
+
+                let mut parser = Parser::with_grammar(&set, grammar);
+                let chunked = feed_in_chunks(&mut parser, input.as_bytes(), &mut rng);
+                assert_eq!(whole, chunked, "seed {} {:?}", seed, grammar);
+
+                // The same parser must give the same result for the next stream
+                let chunked = feed_in_chunks(&mut parser, input.as_bytes(), &mut rng);
+                assert_eq!(whole, chunked, "seed {} {:?}", seed, grammar);
+
+                let mut bytewise = Vec::new();
+                for b in input.as_bytes() {
+                    bytewise.extend(parser.feed(std::slice::from_ref(b)));
+                }
+                parser.finish();
+                assert_eq!(whole, bytewise, "seed {} {:?}", seed, grammar);
+            }
+        }
+    }
+}
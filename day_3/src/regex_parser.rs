@@ -0,0 +1,121 @@
+use crate::grammar::Grammar;
+use crate::machine::OverflowError;
+use regex::Regex;
+
+/// Regex implementation of both parts, kept as a cross-check for `Parser` and `Scanner`.
+/// The toggles take effect at their `(`, as in the other backends.
+pub fn parse_regex(input: &str, allow_non_mul: bool) -> Result<i64, OverflowError> {
+    parse_regex_with_grammar(input, allow_non_mul, Grammar::default())
+}
+
+pub fn parse_regex_with_grammar(
+    input: &str,
+    allow_non_mul: bool,
+    grammar: Grammar,
+) -> Result<i64, OverflowError> {
+    let regex = Regex::new(&pattern(allow_non_mul, grammar)).unwrap();
+
+    let mut enabled = true;
+    let mut result: i64 = 0;
+
+    for captures in regex.captures_iter(input) {
+        if captures.name("do").is_some() {
+            enabled = true;
+        } else if captures.name("dont").is_some() {
+            enabled = false;
+        } else if enabled {
+            if let (Ok(a), Ok(b)) = (captures["a"].parse::<i64>(), captures["b"].parse::<i64>()) {
+                result = a
+                    .checked_mul(b)
+                    .and_then(|product| result.checked_add(product))
+                    .ok_or_else(|| OverflowError {
+                        instruction: "mul",
+                        span: captures.get(0).unwrap().range(),
+                    })?;
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+// `[0-9]` rather than `\d`, which would also accept non-ASCII digits,
+// and the ASCII whitespace set to match `u8::is_ascii_whitespace`.
+fn pattern(allow_non_mul: bool, grammar: Grammar) -> String {
+    let ws = if grammar.allow_whitespace {
+        r"[\t\n\x0C\r ]*"
+    } else {
+        ""
+    };
+    let sign = if grammar.allow_signs { "[+-]?" } else { "" };
+    let digits = match grammar.max_digits {
+        Some(max) => format!("[0-9]{{1,{}}}", max),
+        None => "[0-9]+".to_string(),
+    };
+    let operand = |name: &str| format!("{ws}(?P<{name}>{sign}{digits}){ws}");
+
+    let mul = format!(r"mul\({},{}\)", operand("a"), operand("b"));
+
+    if allow_non_mul {
+        format!(r"{mul}|(?P<do>do\()|(?P<dont>don't\()")
+    } else {
+        mul
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generate::corrupted_memory;
+    use crate::instruction::InstructionSet;
+    use crate::machine::Machine;
+    use crate::parser::Parser;
+    use crate::scanner::Scanner;
+
+    #[test]
+    fn test_examples() {
+        let input = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        assert_eq!(parse_regex(input, false), Ok(161));
+        assert_eq!(parse_regex(input, true), Ok(48));
+    }
+
+    #[test]
+    fn test_backends_agree_on_generated_input() {
+        let grammars = [
+            Grammar::default(),
+            Grammar::lenient(),
+            Grammar {
+                max_digits: None,
+                ..Grammar::default()
+            },
+            Grammar {
+                allow_whitespace: true,
+                ..Grammar::default()
+            },
+            Grammar {
+                allow_signs: true,
+                ..Grammar::default()
+            },
+        ];
+
+        for seed in 0..200 {
+            let input = corrupted_memory(seed, 2_000);
+
+            for grammar in grammars {
+                for (allow_non_mul, instructions) in [
+                    (false, InstructionSet::part_1()),
+                    (true, InstructionSet::part_2()),
+                ] {
+                    let program = Parser::with_grammar(&instructions, grammar).parse(&input);
+                    let parsed = Machine::new().run(&program, &instructions);
+                    let scanned = Scanner::with_grammar(input.as_bytes(), &instructions, grammar)
+                        .run(&mut Machine::new());
+                    let matched = parse_regex_with_grammar(&input, allow_non_mul, grammar);
+
+                    assert_eq!(parsed, matched, "seed {} {:?}: {:?}", seed, grammar, input);
+                    assert_eq!(scanned, matched, "seed {} {:?}: {:?}", seed, grammar, input);
+                }
+            }
+        }
+    }
+}
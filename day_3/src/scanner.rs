@@ -0,0 +1,343 @@
+use crate::grammar::Grammar;
+use crate::instruction::{Arity, InstructionSet, MAX_ARGS};
+use crate::machine::{Machine, OverflowError};
+use std::ops::Range;
+
+/// A call found by the `Scanner`. Unlike `Call` it owns no heap memory.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ScannedCall {
+    pub instruction: usize,
+    args: [i64; MAX_ARGS],
+    len: usize,
+    start: usize,
+    end: usize,
+}
+
+impl ScannedCall {
+    pub fn args(&self) -> &[i64] {
+        &self.args[..self.len]
+    }
+
+    pub fn span(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+/// Allocation-free alternative to `Parser` that works directly on bytes.
+///
+/// Outside of an argument list only `(` matters, so the scanner jumps from one `(` to the next
+/// and resolves the name by looking back at most `max_name_len` bytes. Operands are
+/// accumulated as they are read instead of being collected into a buffer first.
+pub struct Scanner<'a> {
+    input: &'a [u8],
+    pos: usize,
+    instructions: &'a InstructionSet,
+    grammar: Grammar,
+    lookbehind: usize,
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(input: &'a [u8], instructions: &'a InstructionSet) -> Self {
+        Self::with_grammar(input, instructions, Grammar::default())
+    }
+
+    pub fn with_grammar(
+        input: &'a [u8],
+        instructions: &'a InstructionSet,
+        grammar: Grammar,
+    ) -> Self {
+        Self {
+            input,
+            pos: 0,
+            instructions,
+            grammar,
+            lookbehind: instructions.max_name_len(),
+        }
+    }
+
+    /// Executes every remaining call and returns the accumulator.
+    /// Stops at the first call that overflows.
+    pub fn run(self, machine: &mut Machine) -> Result<i64, OverflowError> {
+        let instructions = self.instructions;
+
+        for call in self {
+            machine.execute(instructions, call.instruction, call.args(), call.span())?;
+        }
+
+        Ok(machine.acc)
+    }
+
+    // Reads the argument list following a resolved `(`. On failure `pos` is left on the
+    // offending byte, since it may be the start of the next call.
+    fn read_args(&mut self, instruction: usize, start: usize) -> Option<ScannedCall> {
+        let mut call = ScannedCall {
+            instruction,
+            args: [0; MAX_ARGS],
+            len: 0,
+            start,
+            end: 0,
+        };
+
+        let mut operand = Operand::default();
+
+        while let Some(&b) = self.input.get(self.pos) {
+            match b {
+                b'0'..=b'9' => {
+                    if operand.closed || !self.grammar.digits_allowed(operand.digits + 1) {
+                        return None;
+                    }
+                    operand.push_digit(b - b'0');
+                }
+                b'+' | b'-' if self.grammar.allow_signs && operand.is_empty() => {
+                    operand.sign = true;
+                    operand.negative = b == b'-';
+                }
+                b if b.is_ascii_whitespace()
+                    && self.grammar.allow_whitespace
+                    && (operand.is_empty() || operand.digits > 0) =>
+                {
+                    operand.closed = !operand.is_empty();
+                }
+                b',' => {
+                    if operand.digits == 0 || call.len + 1 >= MAX_ARGS {
+                        return None;
+                    }
+                    call.args[call.len] = operand.value?;
+                    call.len += 1;
+                    operand = Operand::default();
+                }
+                b')' => {
+                    self.pos += 1;
+
+                    if operand.digits > 0 {
+                        call.args[call.len] = operand.value?;
+                        call.len += 1;
+                    } else if operand.sign || call.len > 0 {
+                        return None;
+                    }
+
+                    call.end = self.pos;
+
+                    return self
+                        .instructions
+                        .get(instruction)
+                        .arity
+                        .accepts(call.len)
+                        .then_some(call);
+                }
+                _ => return None,
+            }
+
+            self.pos += 1;
+        }
+
+        None
+    }
+}
+
+impl Iterator for Scanner<'_> {
+    type Item = ScannedCall;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(offset) = self.input[self.pos..].iter().position(|&b| b == b'(') {
+            let open = self.pos + offset;
+            self.pos = open + 1;
+
+            // Most `(` in corrupted memory aren't preceded by a name at all
+            if open == 0 || !is_name_byte(self.input[open - 1]) {
+                continue;
+            }
+
+            let name = &self.input[open.saturating_sub(self.lookbehind)..open];
+
+            if let Some(instruction) = self.instructions.resolve(name) {
+                let start = open - self.instructions.get(instruction).name.len();
+
+                // Instructions without operands, like the toggles, take effect at the `(`
+                if self.instructions.get(instruction).arity == Arity::Exact(0) {
+                    return Some(ScannedCall {
+                        instruction,
+                        args: [0; MAX_ARGS],
+                        len: 0,
+                        start,
+                        end: self.pos,
+                    });
+                }
+
+                if let Some(call) = self.read_args(instruction, start) {
+                    return Some(call);
+                }
+            }
+        }
+
+        self.pos = self.input.len();
+        None
+    }
+}
+
+// An operand accumulated as its bytes are read, matching `str::parse::<i64>` on the same text
+struct Operand {
+    sign: bool,
+    negative: bool,
+    digits: usize,
+    // `None` once the operand has overflowed
+    value: Option<i64>,
+    closed: bool,
+}
+
+impl Default for Operand {
+    fn default() -> Self {
+        Self {
+            sign: false,
+            negative: false,
+            digits: 0,
+            value: Some(0),
+            closed: false,
+        }
+    }
+}
+
+impl Operand {
+    fn is_empty(&self) -> bool {
+        !self.sign && self.digits == 0
+    }
+
+    fn push_digit(&mut self, digit: u8) {
+        self.digits += 1;
+        self.value = self.value.and_then(|v| v.checked_mul(10)).and_then(|v| {
+            if self.negative {
+                v.checked_sub(digit as i64)
+            } else {
+                v.checked_add(digit as i64)
+            }
+        });
+    }
+}
+
+fn is_name_byte(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'\''
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generate::corrupted_memory;
+    use crate::parser::Parser;
+
+    const GRAMMARS: [Grammar; 4] = [
+        Grammar {
+            max_digits: Some(3),
+            allow_whitespace: false,
+            allow_signs: false,
+        },
+        Grammar {
+            max_digits: None,
+            allow_whitespace: false,
+            allow_signs: false,
+        },
+        Grammar {
+            max_digits: Some(3),
+            allow_whitespace: true,
+            allow_signs: true,
+        },
+        Grammar {
+            max_digits: None,
+            allow_whitespace: true,
+            allow_signs: true,
+        },
+    ];
+
+    fn assert_same_as_parser(input: &str, instructions: &InstructionSet) {
+        for grammar in GRAMMARS {
+            assert_same_as_parser_with(input, instructions, grammar);
+        }
+    }
+
+    fn assert_same_as_parser_with(input: &str, instructions: &InstructionSet, grammar: Grammar) {
+        let parsed = Parser::with_grammar(instructions, grammar).parse(input);
+        let scanned =
+            Scanner::with_grammar(input.as_bytes(), instructions, grammar).collect::<Vec<_>>();
+
+        assert_eq!(parsed.len(), scanned.len(), "{:?}", input);
+        for (p, s) in parsed.iter().zip(&scanned) {
+            assert_eq!(p.instruction, s.instruction, "{:?}", input);
+            assert_eq!(p.args, s.args(), "{:?}", input);
+            assert_eq!(p.span, s.span(), "{:?}", input);
+        }
+
+        assert_eq!(
+            Machine::new().run(&parsed, instructions),
+            Scanner::with_grammar(input.as_bytes(), instructions, grammar).run(&mut Machine::new())
+        );
+    }
+
+    #[test]
+    fn test_examples() {
+        let input = b"xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        let part_1 = InstructionSet::part_1();
+        let part_2 = InstructionSet::part_2();
+        assert_eq!(
+            Scanner::new(input, &part_1).run(&mut Machine::new()),
+            Ok(161)
+        );
+        assert_eq!(
+            Scanner::new(input, &part_2).run(&mut Machine::new()),
+            Ok(48)
+        );
+    }
+
+    #[test]
+    fn test_edge_cases_match_parser() {
+        let cases = [
+            "mul(3a,4)",
+            "2mul(3,4)",
+            "mul(,4)",
+            "mul(4,)",
+            "mul(2,mul(3,4)",
+            "mul(2(mul(3,4)",
+            "mul(0000000000000000000012,2)",
+            "mul(2147483647,1)mul(2147483648,1)",
+            "mul(-2147483648,1)mul(-2147483649,1)",
+            "mul(9223372036854775807,1)mul(9223372036854775808,1)",
+            "mul(-9223372036854775808,1)mul(-9223372036854775809,1)",
+            "mul(9999999999,9999999999)mul(2,3)",
+            "mul(999,999)mul(1000,1)mul(0001,1)",
+            "mul( 2 , 4 )mul(1 2,3)mul (2,4)",
+            "mul(-2,+4)mul(--2,4)mul(2-,4)mul(-,4)mul(- 2,4)",
+            "mul(2,4 )mul( ,4)mul(2, )",
+            "do( )don't( )",
+            "émul(2,3)",
+            "mul(2,3",
+            "don't(mul(2,3)",
+            "don't()do(mul(2,3)",
+            "do()don't()",
+        ];
+
+        for case in cases {
+            assert_same_as_parser(case, &InstructionSet::part_1());
+            assert_same_as_parser(case, &InstructionSet::part_2());
+        }
+    }
+
+    #[test]
+    fn test_variadic_limit_matches_parser() {
+        let mut set = InstructionSet::new();
+        set.register("sum", Arity::Variadic { min: 0 }, |machine, args| {
+            machine.add(args.iter().sum())
+        });
+
+        for n in [0, 1, MAX_ARGS - 1, MAX_ARGS, MAX_ARGS + 1] {
+            let args = vec!["1"; n].join(",");
+            assert_same_as_parser(&format!("sum({})", args), &set);
+        }
+    }
+
+    #[test]
+    fn test_generated_input_matches_parser() {
+        for seed in 0..200 {
+            let input = corrupted_memory(seed, 2_000);
+            assert_same_as_parser(&input, &InstructionSet::part_1());
+            assert_same_as_parser(&input, &InstructionSet::part_2());
+        }
+    }
+}
@@ -0,0 +1,302 @@
+//! Structured fuzzing of the corrupted memory parsers.
+//!
+//! Inputs are built from well-formed calls, near misses (calls with a character inserted,
+//! deleted or replaced) and noise. Every backend must agree with `reference`, a deliberately
+//! naive implementation of the grammar that tries each position in turn.
+//!
+//! `test_edge_cases` below runs the same checks on hand-picked inputs that random ones rarely
+//! produce: a `(` inside an argument list, names that end in other names, toggles followed by
+//! junk and operands at the limits of `i64`.
+
+use day_3::annotate::{annotate, render, Format};
+use day_3::grammar::Grammar;
+use day_3::instruction::{Arity, InstructionSet, MAX_ARGS};
+use day_3::machine::Machine;
+use day_3::parser::Parser;
+use day_3::scanner::Scanner;
+use proptest::prelude::*;
+use std::ops::Range;
+
+type RefCall = (usize, Vec<i64>, Range<usize>);
+
+fn reference(input: &[u8], instructions: &InstructionSet, grammar: Grammar) -> Vec<RefCall> {
+    let mut calls = Vec::new();
+    let mut i = 0;
+
+    // A `(` whose name was already tried. The first name tried for a `(` is the longest one,
+    // and a shorter name ending at the same `(` doesn't get a second chance.
+    let mut tried = None;
+
+    'outer: while i < input.len() {
+        for index in 0..instructions.len() {
+            let name = instructions.get(index).name.as_bytes();
+            let open = i + name.len();
+
+            if !input[i..].starts_with(name)
+                || input.get(open) != Some(&b'(')
+                || tried == Some(open)
+            {
+                continue;
+            }
+
+            tried = Some(open);
+
+            // Instructions without operands, like the toggles, take effect at the `(`
+            if instructions.get(index).arity == Arity::Exact(0) {
+                calls.push((index, Vec::new(), i..open + 1));
+                i = open + 1;
+                continue 'outer;
+            }
+
+            if let Some((args, end)) = reference_args(input, open + 1, grammar) {
+                if instructions.get(index).arity.accepts(args.len()) {
+                    calls.push((index, args, i..end));
+                    i = end;
+                    continue 'outer;
+                }
+            }
+        }
+
+        i += 1;
+    }
+
+    calls
+}
+
+// Everything up to the next `)` must be a comma separated list of operands
+fn reference_args(input: &[u8], start: usize, grammar: Grammar) -> Option<(Vec<i64>, usize)> {
+    let close = start + input[start..].iter().position(|b| *b == b')')?;
+    let body = std::str::from_utf8(&input[start..close]).ok()?;
+
+    let trim = |s: &'_ str| -> String {
+        if grammar.allow_whitespace {
+            s.trim_matches(|c: char| c.is_ascii_whitespace())
+                .to_string()
+        } else {
+            s.to_string()
+        }
+    };
+
+    if trim(body).is_empty() {
+        return Some((Vec::new(), close + 1));
+    }
+
+    let mut args = Vec::new();
+    for piece in body.split(',') {
+        let piece = trim(piece);
+        let digits = match piece.strip_prefix(['+', '-']) {
+            Some(digits) if grammar.allow_signs => digits,
+            Some(_) => return None,
+            None => piece.as_str(),
+        };
+
+        if digits.is_empty()
+            || !digits.bytes().all(|b| b.is_ascii_digit())
+            || !grammar.digits_allowed(digits.len())
+        {
+            return None;
+        }
+
+        args.push(piece.parse::<i64>().ok()?);
+    }
+
+    (args.len() <= MAX_ARGS).then_some((args, close + 1))
+}
+
+fn instruction_sets() -> Vec<InstructionSet> {
+    let mut custom = InstructionSet::part_2();
+    custom.register("add", Arity::Exact(2), |machine, args| {
+        machine.add(args[0].checked_add(args[1]).unwrap_or(0))
+    });
+    custom.register("sum", Arity::Variadic { min: 1 }, |machine, args| {
+        machine.add(args.iter().fold(0i64, |a, b| a.wrapping_add(*b)))
+    });
+    custom.register("t", Arity::Exact(1), |machine, _| {
+        machine.enabled = !machine.enabled;
+        Ok(())
+    });
+
+    vec![InstructionSet::part_1(), InstructionSet::part_2(), custom]
+}
+
+fn grammars() -> [Grammar; 4] {
+    [
+        Grammar::default(),
+        Grammar::lenient(),
+        Grammar {
+            allow_whitespace: true,
+            ..Grammar::default()
+        },
+        Grammar {
+            max_digits: None,
+            allow_signs: true,
+            ..Grammar::default()
+        },
+    ]
+}
+
+fn check(input: &str) {
+    for instructions in instruction_sets() {
+        for grammar in grammars() {
+            check_with(input, &instructions, grammar);
+        }
+    }
+}
+
+fn check_with(input: &str, instructions: &InstructionSet, grammar: Grammar) {
+    let expected = reference(input.as_bytes(), instructions, grammar);
+
+    let parsed: Vec<RefCall> = Parser::with_grammar(instructions, grammar)
+        .parse(input)
+        .into_iter()
+        .map(|c| (c.instruction, c.args, c.span))
+        .collect();
+    assert_eq!(parsed, expected, "Parser, {:?}", grammar);
+
+    let scanned: Vec<RefCall> = Scanner::with_grammar(input.as_bytes(), instructions, grammar)
+        .map(|c| (c.instruction, c.args().to_vec(), c.span()))
+        .collect();
+    assert_eq!(scanned, expected, "Scanner, {:?}", grammar);
+
+    let mut parser = Parser::with_grammar(instructions, grammar);
+    let mut chunked = Vec::new();
+    for chunk in input.as_bytes().chunks(7) {
+        chunked.extend(parser.feed(chunk));
+    }
+    parser.finish();
+
+    let program = Parser::with_grammar(instructions, grammar).parse(input);
+    assert_eq!(chunked, program, "chunked Parser, {:?}", grammar);
+
+    let result = Machine::new().run(&program, instructions);
+    let scanner = Scanner::with_grammar(input.as_bytes(), instructions, grammar);
+    assert_eq!(scanner.run(&mut Machine::new()), result);
+
+    // The regex backend only knows the two puzzle instruction sets
+    #[cfg(feature = "regex")]
+    if let Some(allow_non_mul) = match instructions.len() {
+        1 => Some(false),
+        3 => Some(true),
+        _ => None,
+    } {
+        assert_eq!(
+            day_3::regex_parser::parse_regex_with_grammar(input, allow_non_mul, grammar),
+            result,
+            "regex, {:?}",
+            grammar
+        );
+    }
+
+    let annotations = annotate(input, instructions, grammar);
+    render(input, &annotations, instructions, Format::Ansi);
+    render(input, &annotations, instructions, Format::Html);
+}
+
+fn operand() -> impl Strategy<Value = String> {
+    prop_oneof![
+        (0u32..1000).prop_map(|n| n.to_string()),
+        "[0-9]{1,4}",
+        "[+-]?[0-9]{1,20}",
+        Just("9223372036854775807".to_string()),
+        Just("-9223372036854775808".to_string()),
+    ]
+}
+
+fn call() -> impl Strategy<Value = String> {
+    let name = prop_oneof![
+        Just("mul"),
+        Just("do"),
+        Just("don't"),
+        Just("add"),
+        Just("sum"),
+        Just("t"),
+        Just("xmul"),
+        Just("undo"),
+    ];
+    let sep = prop_oneof![4 => Just(""), 1 => Just(" ")];
+
+    (name, prop::collection::vec((operand(), sep), 0..4)).prop_map(|(name, args)| {
+        let args = args
+            .into_iter()
+            .map(|(operand, sep)| format!("{}{}{}", sep, operand, sep))
+            .collect::<Vec<_>>();
+        format!("{}({})", name, args.join(","))
+    })
+}
+
+fn near_miss() -> impl Strategy<Value = String> {
+    let noise = prop::sample::select(vec![
+        '(', ')', ',', ' ', '\n', '\'', '+', '-', 'm', 'u', 'l', 'd', 'o', '0', '9', 'é', '[',
+    ]);
+
+    (call(), any::<prop::sample::Index>(), noise, 0..3u8).prop_map(|(call, at, c, mutation)| {
+        let mut chars = call.chars().collect::<Vec<_>>();
+        let at = at.index(chars.len());
+        match mutation {
+            0 => chars.insert(at, c),
+            1 => {
+                chars.remove(at);
+            }
+            _ => chars[at] = c,
+        }
+        chars.into_iter().collect()
+    })
+}
+
+fn memory() -> impl Strategy<Value = String> {
+    let piece = prop_oneof![
+        3 => call(),
+        3 => near_miss(),
+        2 => "[a-z()',+ 0-9-]{1,3}",
+        1 => "\\PC{1,2}",
+    ];
+
+    prop::collection::vec(piece, 0..24).prop_map(|pieces| pieces.concat())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn backends_match_reference(input in memory()) {
+        check(&input);
+    }
+
+    #[test]
+    fn arbitrary_bytes_never_panic(bytes in prop::collection::vec(any::<u8>(), 0..256)) {
+        for instructions in instruction_sets() {
+            for grammar in grammars() {
+                let mut parser = Parser::with_grammar(&instructions, grammar);
+                let program = parser.feed(&bytes);
+                parser.finish();
+                let _ = Machine::new().run(&program, &instructions);
+                let _ = Scanner::with_grammar(&bytes, &instructions, grammar).run(&mut Machine::new());
+            }
+        }
+    }
+}
+
+#[test]
+fn test_edge_cases() {
+    let cases = [
+        // A name before a `(` that no instruction matches, including with an empty set
+        "a(",
+        // A `(` inside an argument list must not resolve the name before the first one
+        "mul(2(mul(3,4)",
+        // A second name inside an argument list starts a new call
+        "mul(2,mul(3,4)",
+        // Only the suffix of a letter run is the name
+        "xdon't()mulmul(3,3)",
+        // Product overflow with the widest operands
+        "mul(-9223372036854775808,-1)",
+        "mul( -1 ,+2 )mul(- 1,2)mul(1 2,3)",
+        // Toggles take effect at the `(`, whatever follows it
+        "do( )don't( )",
+        "don't(mul(2,3)do(mul(4,5)",
+    ];
+
+    for case in cases {
+        check(case);
+        check_with(case, &InstructionSet::new(), Grammar::default());
+    }
+}
@@ -0,0 +1,39 @@
+use day_4::automaton::MultiSearch;
+use day_4::generate::letter_grid;
+use day_4::grid::load;
+use day_4::search::{Directions, WordSearch};
+use shared::rng::Rng;
+use std::hint::black_box;
+use std::time::Instant;
+
+const SIZE: usize = 300;
+const WORDS: usize = 2000;
+const ALPHABET: [char; 8] = ['A', 'E', 'L', 'N', 'R', 'S', 'T', 'X'];
+
+fn main() {
+    let grid = load(&letter_grid(1, SIZE, SIZE, &ALPHABET)).unwrap();
+
+    let mut rng = Rng::new(2);
+    let words = (0..WORDS)
+        .map(|_| {
+            let len = 3 + rng.below(6);
+            (0..len).map(|_| rng.pick(&ALPHABET)).collect::<String>()
+        })
+        .collect::<Vec<_>>();
+    let words = words.iter().map(|w| w.as_str()).collect::<Vec<_>>();
+
+    let start = Instant::now();
+    let per_cell = WordSearch::new(&words, Directions::All).count(black_box(&grid));
+    println!("WordSearch: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let automaton = MultiSearch::new(&words, Directions::All).count(black_box(&grid));
+    println!("MultiSearch: {:?}", start.elapsed());
+
+    assert_eq!(per_cell, automaton);
+    println!(
+        "{} words, {} occurrences",
+        automaton.len(),
+        automaton.values().sum::<usize>()
+    );
+}
@@ -0,0 +1,107 @@
+use day_4::generate::letter_grid;
+use day_4::grid::load;
+use day_4::search::{Directions, WordSearch};
+use std::collections::BTreeMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const SIZE: usize = 1000;
+
+fn main() {
+    let input = letter_grid(1, SIZE, SIZE, &['X', 'M', 'A', 'S']);
+
+    let map = btree::read_into_map(&input, SIZE as u32);
+    let start = Instant::now();
+    let expected = btree::word_count(black_box(&map), SIZE as u32, SIZE as u32);
+    let before = report("BTreeMap", start);
+
+    let grid = load(&input).unwrap();
+    let search = WordSearch::new(&["XMAS"], Directions::All);
+    let start = Instant::now();
+    let counts = search.count(black_box(&grid));
+    let after = report("Grid", start);
+
+    assert_eq!(counts["XMAS"], expected);
+    println!("XMAS count: {}", expected);
+    println!(
+        "Speedup: {:.1}x",
+        before.as_secs_f64() / after.as_secs_f64()
+    );
+}
+
+fn report(name: &str, start: Instant) -> Duration {
+    let elapsed = start.elapsed();
+    println!(
+        "{}: {:?} ({:.1} Mcells/s)",
+        name,
+        elapsed,
+        (SIZE * SIZE) as f64 / elapsed.as_secs_f64() / 1e6
+    );
+    elapsed
+}
+
+// The search as it was before `Grid`, kept as a baseline
+mod btree {
+    use super::*;
+
+    pub fn read_into_map(input: &str, cols: u32) -> BTreeMap<(u32, u32), char> {
+        let mut chars = BTreeMap::new();
+
+        for (index, c) in input.chars().filter(|c| *c != '\n').enumerate() {
+            let index = index as u32;
+            chars.insert((index / cols, index % cols), c);
+        }
+
+        chars
+    }
+
+    const REL_NEIGHBORS: [(i32, i32); 4] = [(0, 1), (1, 0), (1, -1), (1, 1)];
+    const SOLVED_XMAS: [&str; 2] = ["XMAS", "SAMX"];
+    const VALID_XMAS_SEQ: [&str; 6] = ["X", "XM", "XMA", "S", "SA", "SAM"];
+
+    pub fn word_count(map: &BTreeMap<(u32, u32), char>, rows: u32, cols: u32) -> usize {
+        let mut count = 0;
+
+        for row in 0..rows {
+            for col in 0..cols {
+                count += REL_NEIGHBORS
+                    .iter()
+                    .filter(|direction| find_word(row, col, direction, map, &mut String::new()))
+                    .count();
+            }
+        }
+
+        count
+    }
+
+    fn find_word(
+        row: u32,
+        col: u32,
+        direction: &(i32, i32),
+        map: &BTreeMap<(u32, u32), char>,
+        previous: &mut String,
+    ) -> bool {
+        let Some(current) = map.get(&(row, col)) else {
+            return false;
+        };
+
+        previous.push(*current);
+
+        if SOLVED_XMAS.contains(&previous.as_ref()) {
+            return true;
+        }
+
+        if !VALID_XMAS_SEQ.contains(&previous.as_ref()) {
+            return false;
+        }
+
+        let (dr, dc) = direction;
+        let (nr, nc) = (row as i32 + dr, col as i32 + dc);
+
+        if nr < 0 || nc < 0 {
+            return false;
+        }
+
+        find_word(nr as u32, nc as u32, direction, map, previous)
+    }
+}
@@ -0,0 +1,67 @@
+use day_4::generate::letter_grid;
+use day_4::grid::load;
+use day_4::search::{Directions, WordSearch};
+use day_4::stencil::{PatternSearch, Symmetry, Template};
+use rayon::ThreadPoolBuilder;
+use std::hint::black_box;
+use std::thread::available_parallelism;
+use std::time::{Duration, Instant};
+
+// `GRID_SIZE` overrides the side of the generated square grid
+const DEFAULT_SIZE: usize = 10_000;
+
+fn main() {
+    let size = std::env::var("GRID_SIZE")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_SIZE);
+
+    let grid = load(&letter_grid(1, size, size, &['X', 'M', 'A', 'S'])).unwrap();
+    let words = WordSearch::new(&["XMAS"], Directions::All);
+    let x_mas = PatternSearch::new(
+        &Template::parse("M.S\n.A.\nM.S").unwrap(),
+        Symmetry::Rotations,
+    );
+
+    let start = Instant::now();
+    let expected = (words.count(&grid), x_mas.count(&grid));
+    println!("{}x{} grid, sequential: {:?}", size, size, start.elapsed());
+
+    let max = available_parallelism().map_or(1, |n| n.get());
+    let mut threads = (0..)
+        .map(|i| 1 << i)
+        .take_while(|n| *n < max)
+        .collect::<Vec<_>>();
+    threads.push(max);
+
+    let mut single = Duration::ZERO;
+
+    for n in threads {
+        let pool = ThreadPoolBuilder::new().num_threads(n).build().unwrap();
+
+        let start = Instant::now();
+        let found = pool.install(|| {
+            (
+                words.par_count(black_box(&grid)),
+                x_mas.par_count(black_box(&grid)),
+            )
+        });
+        let elapsed = start.elapsed();
+
+        assert_eq!(found, expected);
+
+        if n == 1 {
+            single = elapsed;
+        }
+
+        println!(
+            "{} threads: {:?} ({:.2}x)",
+            n,
+            elapsed,
+            single.as_secs_f64() / elapsed.as_secs_f64()
+        );
+    }
+
+    println!("XMAS count: {}", expected.0["XMAS"]);
+    println!("MAS crossed count: {}", expected.1);
+}
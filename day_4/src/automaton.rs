@@ -0,0 +1,286 @@
+use crate::search::{prepare, Directions, Word};
+use shared::grid::Grid;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// Aho-Corasick automaton over `char`s, reporting overlapping matches.
+pub struct Automaton {
+    goto: Vec<HashMap<char, usize>>,
+    fail: Vec<usize>,
+    // Patterns ending in each state, including those reached through fail links
+    output: Vec<Vec<usize>>,
+}
+
+impl Automaton {
+    pub fn new<'a>(patterns: impl IntoIterator<Item = &'a [char]>) -> Self {
+        let mut automaton = Self {
+            goto: vec![HashMap::new()],
+            fail: vec![0],
+            output: vec![Vec::new()],
+        };
+
+        for (index, pattern) in patterns.into_iter().enumerate() {
+            let mut state = 0;
+            for c in pattern {
+                state = match automaton.goto[state].get(c) {
+                    Some(next) => *next,
+                    None => {
+                        automaton.goto.push(HashMap::new());
+                        automaton.fail.push(0);
+                        automaton.output.push(Vec::new());
+
+                        let next = automaton.goto.len() - 1;
+                        automaton.goto[state].insert(*c, next);
+                        next
+                    }
+                };
+            }
+            automaton.output[state].push(index);
+        }
+
+        // Breadth first, so a state's fail link is final before its children need it
+        let mut queue = automaton.goto[0].values().copied().collect::<VecDeque<_>>();
+
+        while let Some(state) = queue.pop_front() {
+            let children = automaton.goto[state]
+                .iter()
+                .map(|(c, next)| (*c, *next))
+                .collect::<Vec<_>>();
+
+            for (c, next) in children {
+                let fail = automaton.step(automaton.fail[state], c);
+                automaton.fail[next] = fail;
+
+                let inherited = automaton.output[fail].clone();
+                automaton.output[next].extend(inherited);
+
+                queue.push_back(next);
+            }
+        }
+
+        automaton
+    }
+
+    fn step(&self, mut state: usize, c: char) -> usize {
+        loop {
+            if let Some(next) = self.goto[state].get(&c) {
+                return *next;
+            }
+            if state == 0 {
+                return 0;
+            }
+            state = self.fail[state];
+        }
+    }
+
+    /// Calls `f` with the pattern index and end position of every match in `text`,
+    /// including matches that overlap.
+    pub fn for_each_match(
+        &self,
+        text: impl IntoIterator<Item = char>,
+        mut f: impl FnMut(usize, usize),
+    ) {
+        let mut state = 0;
+
+        for (pos, c) in text.into_iter().enumerate() {
+            state = self.step(state, c);
+            for pattern in &self.output[state] {
+                f(*pattern, pos);
+            }
+        }
+    }
+}
+
+// Forward step of each family of grid lines: rows, columns, diagonals and anti-diagonals
+const LINES: [(isize, isize); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+
+/// Counts the same occurrences as `WordSearch`, but scans each row, column and diagonal of
+/// the grid once in each direction with an `Automaton` over all words, instead of trying
+/// every word at every cell.
+///
+/// Only bounded grids are supported: words never wrap around an edge, so the counts are those
+/// of `WordSearch::new` and not of `WordSearch::with_boundary` with a wrapping `Boundary`.
+pub struct MultiSearch {
+    words: Vec<Word>,
+    automaton: Automaton,
+}
+
+impl MultiSearch {
+    pub fn new(words: &[&str], directions: Directions) -> Self {
+        let words = prepare(words, directions);
+        let automaton = Automaton::new(words.iter().map(|word| word.chars.as_slice()));
+
+        Self { words, automaton }
+    }
+
+    /// Returns the number of occurrences of each word.
+    pub fn count(&self, grid: &Grid<char>) -> BTreeMap<String, usize> {
+        let mut counts = vec![0; self.words.len()];
+
+        for (dr, dc) in LINES {
+            let forward = (dr, dc);
+            let backward = (-dr, -dc);
+
+            if !self
+                .words
+                .iter()
+                .any(|word| word.steps.contains(&forward) || word.steps.contains(&backward))
+            {
+                continue;
+            }
+
+            for line in lines(grid, forward) {
+                self.automaton
+                    .for_each_match(line.iter().copied(), |word, _| {
+                        if self.words[word].steps.contains(&forward) {
+                            counts[word] += 1;
+                        }
+                    });
+                self.automaton
+                    .for_each_match(line.iter().rev().copied(), |word, _| {
+                        if self.words[word].steps.contains(&backward) {
+                            counts[word] += 1;
+                        }
+                    });
+            }
+        }
+
+        self.words
+            .iter()
+            .zip(counts)
+            .map(|(word, count)| (word.text.clone(), count))
+            .collect()
+    }
+}
+
+// Every line of the grid that runs in `step`, read in that direction
+fn lines(grid: &Grid<char>, step: (isize, isize)) -> Vec<Vec<char>> {
+    match step {
+        (0, 1) => grid.iter_rows().map(|row| row.to_vec()).collect(),
+        (1, 0) => (0..grid.cols())
+            .map(|col| grid.column(col).copied().collect())
+            .collect(),
+        (1, 1) => grid.diagonals().map(|ray| ray.copied().collect()).collect(),
+        (1, -1) => grid
+            .anti_diagonals()
+            .map(|ray| ray.copied().collect())
+            .collect(),
+        _ => unreachable!("not a line direction: {:?}", step),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::EXAMPLE;
+    use crate::generate::letter_grid;
+    use crate::grid::load;
+    use crate::search::WordSearch;
+    use shared::rng::Rng;
+
+    fn find(patterns: &[&str], text: &str) -> Vec<(String, usize)> {
+        let patterns = patterns
+            .iter()
+            .map(|p| p.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let automaton = Automaton::new(patterns.iter().map(|p| p.as_slice()));
+
+        let mut found = Vec::new();
+        automaton.for_each_match(text.chars(), |pattern, end| {
+            found.push((patterns[pattern].iter().collect(), end));
+        });
+        found.sort();
+        found
+    }
+
+    #[test]
+    fn test_automaton() {
+        let found = find(&["he", "she", "his", "hers"], "ushers");
+        assert_eq!(
+            found,
+            [
+                ("he".to_string(), 3),
+                ("hers".to_string(), 5),
+                ("she".to_string(), 3)
+            ]
+        );
+
+        assert_eq!(find(&["aa"], "aaaa").len(), 3);
+        assert_eq!(find(&["a", "aa", "aaa"], "aaa").len(), 6);
+        assert!(find(&["xyz"], "xyxy").is_empty());
+    }
+
+    #[test]
+    fn test_example() {
+        let grid = load(EXAMPLE).unwrap();
+        let counts = MultiSearch::new(&["XMAS", "MAS", "A"], Directions::All).count(&grid);
+
+        assert_eq!(counts["XMAS"], 18);
+        assert_eq!(
+            counts,
+            WordSearch::new(&["XMAS", "MAS", "A"], Directions::All).count(&grid)
+        );
+    }
+
+    fn random_words(rng: &mut Rng, alphabet: &[char], count: usize) -> Vec<String> {
+        (0..count)
+            .map(|_| {
+                let len = 1 + rng.below(5);
+                let word = (0..len).map(|_| rng.pick(alphabet)).collect::<String>();
+
+                // Plenty of palindromes, which are only counted one way
+                if rng.below(4) == 0 {
+                    let reversed = word.chars().rev().skip(1).collect::<String>();
+                    word + &reversed
+                } else {
+                    word
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_matches_word_search() {
+        let alphabet = ['A', 'B', 'C'];
+        let mut rng = Rng::new(7);
+
+        for seed in 0..40 {
+            let rows = 1 + rng.below(12);
+            let cols = 1 + rng.below(12);
+            let grid = load(&letter_grid(seed, rows, cols, &alphabet)).unwrap();
+
+            let words = random_words(&mut rng, &alphabet, 50);
+            let words = words.iter().map(|w| w.as_str()).collect::<Vec<_>>();
+
+            for directions in [
+                Directions::Orthogonal,
+                Directions::Diagonal,
+                Directions::All,
+            ] {
+                assert_eq!(
+                    MultiSearch::new(&words, directions).count(&grid),
+                    WordSearch::new(&words, directions).count(&grid),
+                    "{}x{} grid, {:?}",
+                    rows,
+                    cols,
+                    directions
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_thousands_of_words() {
+        let alphabet = ['X', 'M', 'A', 'S', 'Q'];
+        let grid = load(&letter_grid(3, 60, 80, &alphabet)).unwrap();
+
+        let words = random_words(&mut Rng::new(11), &alphabet, 3000);
+        let words = words.iter().map(|w| w.as_str()).collect::<Vec<_>>();
+
+        let counts = MultiSearch::new(&words, Directions::All).count(&grid);
+        assert_eq!(
+            counts,
+            WordSearch::new(&words, Directions::All).count(&grid)
+        );
+        assert!(counts.values().sum::<usize>() > 0);
+    }
+}
@@ -0,0 +1,146 @@
+use shared::grid::Grid;
+
+/// What happens when a word runs off the edge of the grid. A wrapping edge is joined to the
+/// opposite one, so a word may continue from the right edge to the left (horizontally) or
+/// from the bottom edge to the top (vertically).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Boundary {
+    Bounded,
+    WrapHorizontal,
+    WrapVertical,
+    Torus,
+}
+
+impl Boundary {
+    fn wraps_rows(&self) -> bool {
+        matches!(self, Boundary::WrapVertical | Boundary::Torus)
+    }
+
+    fn wraps_cols(&self) -> bool {
+        matches!(self, Boundary::WrapHorizontal | Boundary::Torus)
+    }
+
+    /// The position one `(dr, dc)` step away from `pos`, if the step doesn't cross an edge
+    /// that is closed.
+    pub fn offset<T>(
+        &self,
+        grid: &Grid<T>,
+        (row, col): (usize, usize),
+        (dr, dc): (isize, isize),
+    ) -> Option<(usize, usize)> {
+        let row = wrap(row, dr, grid.rows(), self.wraps_rows())?;
+        let col = wrap(col, dc, grid.cols(), self.wraps_cols())?;
+
+        grid.contains((row, col)).then_some((row, col))
+    }
+
+    // The step reduced modulo the wrapping dimensions. On a grid with two rows that wrap,
+    // one step down and one step up both lead to the other row.
+    pub(crate) fn effective(
+        &self,
+        (dr, dc): (isize, isize),
+        rows: usize,
+        cols: usize,
+    ) -> (isize, isize) {
+        let reduce = |d: isize, len: usize, wraps: bool| {
+            if wraps {
+                d.rem_euclid(len as isize)
+            } else {
+                d
+            }
+        };
+
+        (
+            reduce(dr, rows, self.wraps_rows()),
+            reduce(dc, cols, self.wraps_cols()),
+        )
+    }
+
+    // How many cells a line in direction `step` passes through before it comes back to where
+    // it started, or `None` if it runs into a closed edge first
+    pub(crate) fn period(&self, step: (isize, isize), rows: usize, cols: usize) -> Option<usize> {
+        let (dr, dc) = self.effective(step, rows, cols);
+
+        if (dr != 0 && !self.wraps_rows()) || (dc != 0 && !self.wraps_cols()) {
+            return None;
+        }
+
+        let cycle = |d: isize, len: usize| {
+            if d == 0 {
+                1
+            } else {
+                len / gcd(d.unsigned_abs(), len)
+            }
+        };
+
+        let (a, b) = (cycle(dr, rows), cycle(dc, cols));
+        Some(a / gcd(a, b) * b)
+    }
+}
+
+fn wrap(index: usize, delta: isize, len: usize, wraps: bool) -> Option<usize> {
+    if wraps {
+        Some((index as isize + delta).rem_euclid(len as isize) as usize)
+    } else {
+        index.checked_add_signed(delta)
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_offset() {
+        let grid = Grid::filled(3, 4, '.');
+
+        assert_eq!(Boundary::Bounded.offset(&grid, (0, 3), (0, 1)), None);
+        assert_eq!(
+            Boundary::WrapHorizontal.offset(&grid, (0, 3), (0, 1)),
+            Some((0, 0))
+        );
+        assert_eq!(
+            Boundary::WrapHorizontal.offset(&grid, (0, 0), (-1, 0)),
+            None
+        );
+        assert_eq!(
+            Boundary::WrapVertical.offset(&grid, (0, 0), (-1, 0)),
+            Some((2, 0))
+        );
+        assert_eq!(Boundary::WrapVertical.offset(&grid, (0, 0), (-1, -1)), None);
+        assert_eq!(
+            Boundary::Torus.offset(&grid, (0, 0), (-1, -1)),
+            Some((2, 3))
+        );
+        assert_eq!(Boundary::Torus.offset(&grid, (2, 3), (1, 1)), Some((0, 0)));
+    }
+
+    #[test]
+    fn test_period() {
+        assert_eq!(Boundary::Bounded.period((0, 1), 3, 4), None);
+        assert_eq!(Boundary::WrapHorizontal.period((0, 1), 3, 4), Some(4));
+        assert_eq!(Boundary::WrapHorizontal.period((1, 1), 3, 4), None);
+        assert_eq!(Boundary::WrapVertical.period((-1, 0), 3, 4), Some(3));
+        assert_eq!(Boundary::Torus.period((1, 1), 3, 4), Some(12));
+        assert_eq!(Boundary::Torus.period((1, -1), 4, 6), Some(12));
+
+        // With a single row, moving diagonally on a torus is moving sideways
+        assert_eq!(Boundary::Torus.period((1, 1), 1, 5), Some(5));
+        assert_eq!(Boundary::Torus.period((1, 0), 1, 5), Some(1));
+    }
+
+    #[test]
+    fn test_effective() {
+        assert_eq!(Boundary::Bounded.effective((-1, 1), 2, 2), (-1, 1));
+        assert_eq!(Boundary::Torus.effective((-1, 1), 2, 5), (1, 1));
+        assert_eq!(Boundary::WrapVertical.effective((-1, -1), 2, 5), (1, -1));
+    }
+}
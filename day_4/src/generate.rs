@@ -0,0 +1,18 @@
+use shared::rng::Rng;
+
+/// A `rows` by `cols` grid of letters drawn from `alphabet`, one line per row.
+pub fn letter_grid(seed: u64, rows: usize, cols: usize, alphabet: &[char]) -> String {
+    let mut rng = Rng::new(seed);
+    let mut grid = String::with_capacity((cols + 1) * rows);
+
+    for row in 0..rows {
+        if row > 0 {
+            grid.push('\n');
+        }
+        for _ in 0..cols {
+            grid.push(rng.pick(alphabet));
+        }
+    }
+
+    grid
+}
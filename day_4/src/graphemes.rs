@@ -0,0 +1,274 @@
+use crate::grid::{dims_by, GridError};
+use shared::grid::Grid;
+use std::collections::HashMap;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// Grapheme clusters of more than one `char` are given codes from the supplementary private
+// use planes, which run from here to U+10FFFD
+const FIRST_CODE: u32 = 0xF0000;
+
+fn is_reserved(c: char) -> bool {
+    c as u32 >= FIRST_CODE
+}
+
+/// Grid cells made of grapheme clusters rather than `char`s, so that a letter with combining
+/// marks, a Hangul syllable in jamo or an emoji sequence takes up one cell.
+///
+/// A cluster of a single `char` is stored as that `char`, and every longer one as a private
+/// use `char` standing in for it. Grids and words encoded by the same table can then be
+/// searched like any other `Grid<char>`, and the results decoded back.
+#[derive(Debug, Default)]
+pub struct Graphemes {
+    clusters: Vec<String>,
+    codes: HashMap<String, char>,
+}
+
+impl Graphemes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a rectangular grid with one grapheme cluster per cell. Every cell must take up
+    /// room when printed, and private use characters are reserved for the encoding.
+    pub fn load(&mut self, input: &str) -> Result<Grid<char>, GridError> {
+        let (rows, cols) = dims_by(input, |line| line.graphemes(true).count())?;
+
+        let mut cells = Vec::with_capacity(rows * cols);
+
+        for (row, line) in input.lines().enumerate() {
+            for (col, cluster) in line.graphemes(true).enumerate() {
+                let (line, column) = (row + 1, col + 1);
+
+                if cluster.chars().any(is_reserved) {
+                    return Err(GridError::Reserved { line, column });
+                }
+                if cluster.width() == 0 {
+                    return Err(GridError::ZeroWidth { line, column });
+                }
+
+                cells.push(self.code(cluster));
+            }
+        }
+
+        Ok(Grid::new(rows, cols, cells))
+    }
+
+    /// Encodes a word to search for in grids loaded by this table. Like a grid, the word may
+    /// not contain private use characters, and errors give its position as line 1.
+    pub fn encode(&mut self, word: &str) -> Result<String, GridError> {
+        word.graphemes(true)
+            .enumerate()
+            .map(|(col, cluster)| {
+                if cluster.chars().any(is_reserved) {
+                    return Err(GridError::Reserved {
+                        line: 1,
+                        column: col + 1,
+                    });
+                }
+                Ok(self.code(cluster))
+            })
+            .collect()
+    }
+
+    /// Turns encoded text, like a word in a match, back into the clusters it stands for.
+    /// Codes this table never handed out are left as they are.
+    pub fn decode(&self, text: &str) -> String {
+        text.chars().map(|c| self.cluster(c)).collect()
+    }
+
+    /// Prints an encoded grid with every cell padded to the width of the widest one, so that
+    /// columns line up when wide and narrow letters are mixed.
+    pub fn render(&self, grid: &Grid<char>) -> String {
+        let width = grid
+            .iter()
+            .map(|(_, c)| self.cluster(*c).width())
+            .max()
+            .unwrap_or(0);
+
+        let mut out = String::new();
+        for row in grid.iter_rows() {
+            for c in row {
+                let cluster = self.cluster(*c);
+                out.push_str(&cluster);
+                out.extend(std::iter::repeat_n(' ', width - cluster.width()));
+            }
+            out.push('\n');
+        }
+
+        out
+    }
+
+    fn code(&mut self, cluster: &str) -> char {
+        let mut chars = cluster.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return c;
+        }
+
+        if let Some(code) = self.codes.get(cluster) {
+            return *code;
+        }
+
+        let code = char::from_u32(FIRST_CODE + self.clusters.len() as u32)
+            .filter(|code| *code <= '\u{10FFFD}')
+            .expect("too many distinct grapheme clusters");
+
+        self.clusters.push(cluster.to_string());
+        self.codes.insert(cluster.to_string(), code);
+        code
+    }
+
+    fn cluster(&self, c: char) -> String {
+        if is_reserved(c) {
+            if let Some(cluster) = self.clusters.get((c as u32 - FIRST_CODE) as usize) {
+                return cluster.clone();
+            }
+        }
+
+        c.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::search::{Directions, WordSearch};
+    use crate::stencil::{PatternSearch, Symmetry, Template};
+
+    // "é" as an "e" followed by a combining acute accent
+    const E_ACUTE: &str = "e\u{301}";
+
+    fn count(input: &str, word: &str, directions: Directions) -> usize {
+        let mut table = Graphemes::new();
+        let grid = table.load(input).unwrap();
+        let word = table.encode(word).unwrap();
+
+        WordSearch::new(&[&word], directions).count(&grid)[&word]
+    }
+
+    #[test]
+    fn test_combining_marks() {
+        let input = format!("CAF{e}\nQQQQ\n{e}FAC", e = E_ACUTE);
+
+        let mut table = Graphemes::new();
+        let grid = table.load(&input).unwrap();
+        assert_eq!((grid.rows(), grid.cols()), (3, 4));
+        assert_eq!(table.decode(&grid[(0, 3)].to_string()), E_ACUTE);
+
+        let cafe = format!("CAF{}", E_ACUTE);
+        assert_eq!(count(&input, &cafe, Directions::All), 2);
+        assert_eq!(count(&input, "CAFE", Directions::All), 0);
+
+        // Split into chars, the rows don't even line up
+        assert!(crate::grid::load(&input).is_err());
+    }
+
+    #[test]
+    fn test_mixed_scripts() {
+        // Hangul written as conjoining jamo, precomposed Hangul, Latin with combining marks,
+        // Greek and emoji sequences, one letter per cell
+        let han = "\u{1112}\u{1161}\u{11AB}";
+        let family = "👨\u{200D}👩\u{200D}👧";
+        let flag = "🇰🇷";
+        let input = format!(
+            "{han}글a{e}\nΩ{family}{flag}{e}\n{han}{flag}Ωz\n글{flag}{han}{family}",
+            han = han,
+            family = family,
+            flag = flag,
+            e = E_ACUTE,
+        );
+
+        let mut table = Graphemes::new();
+        let grid = table.load(&input).unwrap();
+        assert_eq!((grid.rows(), grid.cols()), (4, 4));
+        assert_eq!(table.decode(&grid[(1, 1)].to_string()), family);
+
+        // Down the first column
+        let word = format!("{}Ω{}", han, han);
+        assert_eq!(count(&input, &word, Directions::Orthogonal), 1);
+
+        // Down the diagonal, then back up it
+        let word = format!("{}{}Ω{}", han, family, family);
+        assert_eq!(count(&input, &word, Directions::Diagonal), 1);
+        let word = format!("{}Ω{}{}", family, family, han);
+        assert_eq!(count(&input, &word, Directions::All), 1);
+
+        // Two flags next to each other, but each flag is a single letter
+        assert_eq!(count(&input, flag, Directions::All), 3);
+        assert_eq!(count(&input, "🇰", Directions::All), 0);
+    }
+
+    #[test]
+    fn test_templates() {
+        let input = format!("ㄱ.{e}\n.나.\n{e}.ㄱ", e = E_ACUTE);
+
+        let mut table = Graphemes::new();
+        let grid = table.load(&input).unwrap();
+        let template =
+            Template::parse(&table.encode(&format!("ㄱ.{}\n.나.", E_ACUTE)).unwrap()).unwrap();
+
+        assert_eq!(
+            PatternSearch::new(&template, Symmetry::None).count(&grid),
+            1
+        );
+        assert_eq!(
+            PatternSearch::new(&template, Symmetry::Rotations).count(&grid),
+            2
+        );
+    }
+
+    #[test]
+    fn test_width_validation() {
+        let mut table = Graphemes::new();
+
+        // A combining mark with no letter before it
+        assert_eq!(
+            table.load("AB\n\u{301}B"),
+            Err(GridError::ZeroWidth { line: 2, column: 1 })
+        );
+        assert_eq!(
+            table.load("AB\nA\u{F0000}"),
+            Err(GridError::Reserved { line: 2, column: 2 })
+        );
+        assert_eq!(
+            table.load(&format!("AB\nA{}C", E_ACUTE)),
+            Err(GridError::Ragged {
+                line: 2,
+                expected: 2,
+                found: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let mut table = Graphemes::new();
+        let grid = table.load(&format!("글a\n{}글", E_ACUTE)).unwrap();
+
+        // Narrow letters are padded to line up with the wide ones
+        assert_eq!(table.render(&grid), format!("글a \n{} 글\n", E_ACUTE));
+        let encoded = table.encode("글a").unwrap();
+        assert_eq!(table.decode(&encoded), "글a");
+    }
+
+    #[test]
+    fn test_reserved_words() {
+        let mut table = Graphemes::new();
+        table.load(&format!("{}A", E_ACUTE)).unwrap();
+
+        assert_eq!(
+            table.encode("A\u{F0005}"),
+            Err(GridError::Reserved { line: 1, column: 2 })
+        );
+        assert_eq!(
+            table.encode(&format!("{}\u{10FFFD}", E_ACUTE)),
+            Err(GridError::Reserved { line: 1, column: 2 })
+        );
+
+        // Codes that were never handed out decode to themselves
+        assert_eq!(
+            table.decode("\u{F0000}\u{F0005}"),
+            format!("{}\u{F0005}", E_ACUTE)
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use shared::grid::Grid;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GridError {
+    Empty,
+    /// A line whose length differs from the first line's. Lines are numbered from 1.
+    Ragged {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// A cell that takes up no room when printed, like a combining mark with nothing to
+    /// combine with. Lines and columns are numbered from 1.
+    ZeroWidth {
+        line: usize,
+        column: usize,
+    },
+    /// A character from the private use planes, which stand in for grapheme clusters.
+    Reserved {
+        line: usize,
+        column: usize,
+    },
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridError::Empty => write!(f, "the grid is empty"),
+            GridError::Ragged {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {} has {} cells but line 1 has {}",
+                line, found, expected
+            ),
+            GridError::ZeroWidth { line, column } => {
+                write!(f, "cell {} of line {} has no width", column, line)
+            }
+            GridError::Reserved { line, column } => write!(
+                f,
+                "cell {} of line {} is a reserved private use character",
+                column, line
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GridError {}
+
+/// Returns `(rows, cols)`, checking that every line has the same number of cells.
+pub fn get_dims(input: &str) -> Result<(usize, usize), GridError> {
+    dims_by(input, |line| line.chars().count())
+}
+
+// `get_dims` with `cells` counting the cells in a line
+pub(crate) fn dims_by(
+    input: &str,
+    cells: impl Fn(&str) -> usize,
+) -> Result<(usize, usize), GridError> {
+    let mut lines = input.lines();
+
+    let cols = lines.next().map_or(0, &cells);
+
+    let mut rows = 1;
+
+    for (index, line) in lines.enumerate() {
+        let found = cells(line);
+        if found != cols {
+            return Err(GridError::Ragged {
+                line: index + 2,
+                expected: cols,
+                found,
+            });
+        }
+        rows += 1;
+    }
+
+    if cols == 0 {
+        return Err(GridError::Empty);
+    }
+
+    Ok((rows, cols))
+}
+
+/// Reads a rectangular letter grid, one cell per `char`.
+pub fn load(input: &str) -> Result<Grid<char>, GridError> {
+    let (rows, cols) = get_dims(input)?;
+    let cells = input.lines().flat_map(|line| line.chars()).collect();
+
+    Ok(Grid::new(rows, cols, cells))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_get_dims() {
+        assert_eq!(get_dims("000\n000\n000"), Ok((3, 3)));
+        assert_eq!(get_dims("0000\n0000\n0000\n0000"), Ok((4, 4)));
+    }
+
+    #[test]
+    fn test_get_dims_rectangular() {
+        assert_eq!(get_dims("00000\n00000"), Ok((2, 5)));
+        assert_eq!(get_dims("00\n00\n00\n00"), Ok((4, 2)));
+        assert_eq!(get_dims("0000000"), Ok((1, 7)));
+        assert_eq!(get_dims("0\n0\n0"), Ok((3, 1)));
+        assert_eq!(get_dims("00\r\n00\r\n"), Ok((2, 2)));
+    }
+
+    #[test]
+    fn test_get_dims_rejects_ragged() {
+        assert_eq!(
+            get_dims("000\n000\n00\n000"),
+            Err(GridError::Ragged {
+                line: 3,
+                expected: 3,
+                found: 2,
+            })
+        );
+        assert_eq!(
+            get_dims("00\n000").unwrap_err().to_string(),
+            "line 2 has 3 cells but line 1 has 2"
+        );
+        assert!(get_dims("00\n\n00").is_err());
+        assert_eq!(get_dims(""), Err(GridError::Empty));
+        assert_eq!(get_dims("\n"), Err(GridError::Empty));
+        assert_eq!(
+            get_dims("\n00"),
+            Err(GridError::Ragged {
+                line: 2,
+                expected: 0,
+                found: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn test_load() {
+        let str = "162\n789\n304";
+        let grid = load(str).unwrap();
+        assert_eq!(grid[(0, 0)], '1');
+        assert_eq!(grid[(2, 0)], '3');
+        assert_eq!(grid[(0, 2)], '2');
+        assert_eq!(grid[(2, 2)], '4');
+    }
+
+    #[test]
+    fn test_load_rectangular() {
+        let wide = load("abcde\nfghij").unwrap();
+        assert_eq!((wide.rows(), wide.cols()), (2, 5));
+        assert_eq!(wide[(0, 4)], 'e');
+        assert_eq!(wide[(1, 0)], 'f');
+        assert_eq!(wide.to_string(), "abcde\nfghij");
+
+        let tall = load("ab\ncd\nef\ngh").unwrap();
+        assert_eq!((tall.rows(), tall.cols()), (4, 2));
+        assert_eq!(tall[(3, 1)], 'h');
+        assert_eq!(tall[(1, 0)], 'c');
+
+        let line = load("xyz").unwrap();
+        assert_eq!((line.rows(), line.cols()), (1, 3));
+        assert_eq!(line[(0, 2)], 'z');
+    }
+}
@@ -0,0 +1,74 @@
+use crate::search::Match;
+use shared::grid::Grid;
+use std::collections::BTreeSet;
+
+/// Renders the grid with every letter that isn't part of a match replaced by `.`,
+/// like the illustrations in the puzzle.
+pub fn render(grid: &Grid<char>, matches: &[Match]) -> String {
+    format!("{}\n", dim(grid, matches))
+}
+
+/// The grid that `render` prints, for printing some other way.
+pub fn dim(grid: &Grid<char>, matches: &[Match]) -> Grid<char> {
+    let covered = matches
+        .iter()
+        .flat_map(|m| m.cells.iter().copied())
+        .collect::<BTreeSet<_>>();
+
+    let mut dimmed = grid.clone();
+    for pos in grid.positions() {
+        if !covered.contains(&pos) {
+            dimmed[pos] = '.';
+        }
+    }
+
+    dimmed
+}
+
+/// Serializes the match list as a JSON array.
+pub fn to_json(matches: &[Match]) -> String {
+    serde_json::to_string(matches).expect("matches always serialize")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::EXAMPLE;
+    use crate::grid::load;
+    use crate::search::{Directions, WordSearch};
+
+    // The illustration from the puzzle description
+    const HIGHLIGHTED: &str = "....XXMAS.\n.SAMXMS...\n...S..A...\n..A.A.MS.X\nXMASAMX.MM\nX.....XA.A\nS.S.S.S.SS\n.A.A.A.A.A\n..M.M.M.MM\n.X.X.XMASX\n";
+
+    #[test]
+    fn test_render_example() {
+        let grid = load(EXAMPLE).unwrap();
+        let matches = WordSearch::new(&["XMAS"], Directions::All).matches(&grid);
+
+        assert_eq!(render(&grid, &matches), HIGHLIGHTED);
+    }
+
+    #[test]
+    fn test_render_nothing_found() {
+        let grid = load("ABC\nDEF").unwrap();
+        assert_eq!(render(&grid, &[]), "...\n...\n");
+    }
+
+    #[test]
+    fn test_to_json() {
+        let matches =
+            WordSearch::new(&["XMAS"], Directions::All).matches(&load("XMAS\nQQQQ").unwrap());
+
+        let value: serde_json::Value = serde_json::from_str(&to_json(&matches)).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!([{
+                "word": "XMAS",
+                "start": [0, 0],
+                "direction": [0, 1],
+                "cells": [[0, 0], [0, 1], [0, 2], [0, 3]],
+            }])
+        );
+        assert_eq!(to_json(&[]), "[]");
+    }
+}
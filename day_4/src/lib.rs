@@ -0,0 +1,11 @@
+pub mod automaton;
+pub mod boundary;
+#[cfg(test)]
+mod fixtures;
+pub mod generate;
+pub mod graphemes;
+pub mod grid;
+pub mod highlight;
+pub mod search;
+pub mod stencil;
+pub mod stream;
@@ -1,167 +1,119 @@
+use day_4::automaton::MultiSearch;
+use day_4::graphemes::Graphemes;
+use day_4::grid::load;
+use day_4::highlight::{dim, render, to_json};
+use day_4::search::{Directions, Match, WordSearch};
+use day_4::stencil::{PatternSearch, Symmetry, Template};
+use day_4::stream::StreamSearch;
 use std::collections::BTreeMap;
 
-fn main() {
-    let input = include_str!("../input").trim();
-
-    let (rows, cols) = get_dims(input);
-
-    let map = read_into_map(input, cols);
-
-    let count = word_count(&map, rows, cols);
-
-    println!("XMAS count: {}", count);
-
-    let count2 = word_count_2(&map, rows, cols);
+// Two MAS crossing on their A, each of which may be written backwards
+const X_MAS: &str = "M.S\n.A.\nM.S";
 
-    println!("MAS crossed count: {}", count2);
-}
-
-fn get_dims(input: &str) -> (u32, u32) {
-    (
-        input.chars().position(|c| c == '\n').unwrap() as u32,
-        input.lines().count() as u32,
-    )
+fn exit_invalid(err: impl std::fmt::Display) -> ! {
+    eprintln!("Invalid grid: {}", err);
+    std::process::exit(1);
 }
 
-fn read_into_map(input: &str, cols: u32) -> BTreeMap<(u32, u32), char> {
-    let mut chars: BTreeMap<(u32, u32), char> = BTreeMap::new();
+fn main() {
+    let input = include_str!("../input").trim();
 
-    for (index, c) in input
-        .chars()
-        .filter_map(|c| if c == '\n' { None } else { Some(c) })
-        .enumerate()
-    {
-        let index = index as u32;
-        chars.entry((index / cols, index % cols)).or_insert(c);
+    // `--graphemes` reads one grapheme cluster per cell instead of one `char`, for letters
+    // made of several code points.
+    // `--highlight` prints the grid with only the matches left in, `--json` the match list,
+    // `--automaton` counts with `MultiSearch`, which suits long word lists, and `--stream`
+    // counts in a grid read from stdin without holding all of it in memory.
+    // Any words given on the command line are searched for instead of the puzzle.
+    let mut args = std::env::args().skip(1).peekable();
+    let mut graphemes = args
+        .next_if(|arg| arg == "--graphemes")
+        .map(|_| Graphemes::new());
+    let mode = args
+        .next_if(|arg| ["--highlight", "--json", "--automaton", "--stream"].contains(&arg.as_str()))
+        .unwrap_or_default();
+    let words = args.collect::<Vec<_>>();
+
+    if words.iter().any(|word| word.is_empty()) {
+        eprintln!("Cannot search for an empty word");
+        std::process::exit(1);
     }
 
-    chars
-}
-
-const REL_NEIGHBORS: [(i32, i32); 4] = [(0, 1), (1, 0), (1, -1), (1, 1)];
-
-fn word_count(map: &BTreeMap<(u32, u32), char>, rows: u32, cols: u32) -> i32 {
-    let mut count = 0;
+    let grid = match &mut graphemes {
+        Some(table) => table.load(input),
+        None => load(input),
+    }
+    .unwrap_or_else(|err| exit_invalid(err));
 
-    for row in 0..rows {
-        for col in 0..cols {
-            count += REL_NEIGHBORS
+    if !mode.is_empty() || !words.is_empty() {
+        let mut words = match &mut graphemes {
+            Some(table) => words
                 .iter()
-                .map(|(dr, dc)| find_word(row, col, &(*dr, *dc), &map, &mut "".to_string()))
-                .sum::<i32>();
+                .map(|w| {
+                    table.encode(w).unwrap_or_else(|err| {
+                        eprintln!("Invalid word {}: {}", w, err);
+                        std::process::exit(1);
+                    })
+                })
+                .collect(),
+            None => words,
+        };
+        if words.is_empty() {
+            words.push("XMAS".to_string());
         }
-    }
-
-    count
-}
-
-const SOLVED_XMAS: [&str; 2] = ["XMAS", "SAMX"];
-const VALID_XMAS_SEQ: [&str; 6] = ["X", "XM", "XMA", "S", "SA", "SAM"];
-
-fn find_word(
-    row: u32,
-    col: u32,
-    direction: &(i32, i32),
-    map: &BTreeMap<(u32, u32), char>,
-    previous: &mut String,
-) -> i32 {
-    let Some(current) = map.get(&(row, col)) else {
-        return 0;
-    };
-
-    previous.push(*current);
-
-    if SOLVED_XMAS.contains(&previous.as_ref()) {
-        return 1;
-    }
-
-    if !VALID_XMAS_SEQ.contains(&previous.as_ref()) {
-        return 0;
-    }
-
-    let (dr, dc) = direction;
-    let (nr, nc) = (row as i32 + dr, col as i32 + dc);
-
-    if nr < 0 || nc < 0 {
-        return 0;
-    }
-
-    find_word(nr as u32, nc as u32, direction, map, previous)
-}
-
-const DIR_1: (i32, i32) = (1, 1);
-const DIR_2: (i32, i32) = (1, -1);
-
-fn word_count_2(map: &BTreeMap<(u32, u32), char>, rows: u32, cols: u32) -> i32 {
-    let mut count = 0;
-
-    for row in 0..rows {
-        for col in 0..cols {
-            let (r1, c1) = DIR_1;
-            let (r2, c2) = DIR_2;
+        let words = words.iter().map(|w| w.as_str()).collect::<Vec<_>>();
+
+        let decode = |text: &str| match &graphemes {
+            Some(table) => table.decode(text),
+            None => text.to_string(),
+        };
+        let print_counts = |counts: BTreeMap<String, usize>| {
+            for (word, count) in counts {
+                println!("{}: {}", decode(&word), count);
+            }
+        };
+
+        let search = WordSearch::new(&words, Directions::All);
+
+        match mode.as_str() {
+            "--highlight" => match &graphemes {
+                Some(table) => print!("{}", table.render(&dim(&grid, &search.matches(&grid)))),
+                None => print!("{}", render(&grid, &search.matches(&grid))),
+            },
+            "--json" => {
+                let matches = search
+                    .matches(&grid)
+                    .into_iter()
+                    .map(|m| Match {
+                        word: decode(&m.word),
+                        ..m
+                    })
+                    .collect::<Vec<_>>();
+                println!("{}", to_json(&matches));
+            }
+            "--automaton" => print_counts(MultiSearch::new(&words, Directions::All).count(&grid)),
+            "--stream" => {
+                if graphemes.is_some() {
+                    eprintln!("--stream reads one char per cell and can't take --graphemes");
+                    std::process::exit(1);
+                }
 
-            if find_word_2(row, col, &(r1, c1), &map, &mut "".to_string()) {
-                if find_word_2(row, col + 2, &(r2, c2), &map, &mut "".to_string()) {
-                    count += 1;
+                let stdin = std::io::stdin().lock();
+                match StreamSearch::new(&words, Directions::All).count(stdin) {
+                    Ok(counts) => print_counts(counts),
+                    Err(err) => exit_invalid(err),
                 }
             }
+            _ => print_counts(search.count(&grid)),
         }
+        return;
     }
 
-    count
-}
-
-const SOLVED_MAS: [&str; 2] = ["MAS", "SAM"];
-const VALID_MAS_SEQ: [&str; 4] = ["M", "MA", "S", "SA"];
-
-fn find_word_2(
-    row: u32,
-    col: u32,
-    direction: &(i32, i32),
-    map: &BTreeMap<(u32, u32), char>,
-    previous: &mut String,
-) -> bool {
-    let Some(current) = map.get(&(row, col)) else {
-        return false;
-    };
-
-    previous.push(*current);
-
-    if SOLVED_MAS.contains(&previous.as_ref()) {
-        return true;
-    }
-
-    if !VALID_MAS_SEQ.contains(&previous.as_ref()) {
-        return false;
-    }
+    let counts = WordSearch::new(&["XMAS"], Directions::All).par_count(&grid);
 
-    let (dr, dc) = direction;
-    let (nr, nc) = (row as i32 + dr, col as i32 + dc);
+    println!("XMAS count: {}", counts["XMAS"]);
 
-    if nr < 0 || nc < 0 {
-        return false;
-    }
+    let x_mas = Template::parse(X_MAS).unwrap();
+    let count2 = PatternSearch::new(&x_mas, Symmetry::Rotations).par_count(&grid);
 
-    find_word_2(nr as u32, nc as u32, direction, map, previous)
-}
-
-#[cfg(test)]
-mod test {
-    use super::*;
-
-    #[test]
-    fn test_get_dims() {
-        assert_eq!(get_dims(&"000\n000\n000"), (3, 3));
-        assert_eq!(get_dims(&"0000\n0000\n0000\n0000"), (4, 4));
-    }
-
-    #[test]
-    fn test_read_into_map() {
-        let str = "162\n789\n304";
-        let map = read_into_map(str, 3);
-        assert_eq!(*map.get(&(0, 0)).unwrap(), '1');
-        assert_eq!(*map.get(&(2, 0)).unwrap(), '3');
-        assert_eq!(*map.get(&(0, 2)).unwrap(), '2');
-        assert_eq!(*map.get(&(2, 2)).unwrap(), '4');
-    }
+    println!("MAS crossed count: {}", count2);
 }
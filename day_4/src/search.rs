@@ -0,0 +1,608 @@
+use crate::boundary::Boundary;
+use rayon::prelude::*;
+use serde::Serialize;
+use shared::grid::Grid;
+use std::collections::BTreeMap;
+
+/// Which ways a word may run through the grid.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Directions {
+    Orthogonal,
+    Diagonal,
+    All,
+}
+
+// `(row, col)` steps. Each set contains the opposite of every step in it, after the step.
+const ORTHOGONAL: [(isize, isize); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+const DIAGONAL: [(isize, isize); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+const ALL: [(isize, isize); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+
+impl Directions {
+    pub fn steps(&self) -> &'static [(isize, isize)] {
+        match self {
+            Directions::Orthogonal => &ORTHOGONAL,
+            Directions::Diagonal => &DIAGONAL,
+            Directions::All => &ALL,
+        }
+    }
+}
+
+/// One occurrence of a word. Cells are `(row, col)`, in the order the word is read.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Match {
+    pub word: String,
+    pub start: (usize, usize),
+    pub direction: (isize, isize),
+    pub cells: Vec<(usize, usize)>,
+}
+
+pub(crate) struct Word {
+    pub(crate) text: String,
+    pub(crate) chars: Vec<char>,
+    // The directions an occurrence may be read in, when the grid is bounded
+    pub(crate) steps: Vec<(isize, isize)>,
+}
+
+// Deduplicates `words` and sorts them, which is the order counts and matches are reported in
+pub(crate) fn prepare(words: &[&str], directions: Directions) -> Vec<Word> {
+    let mut unique: Vec<&str> = words.to_vec();
+    unique.sort_unstable();
+    unique.dedup();
+
+    unique
+        .into_iter()
+        .map(|text| {
+            assert!(!text.is_empty(), "cannot search for an empty word");
+
+            let chars = text.chars().collect::<Vec<_>>();
+            let steps = plan(&chars, directions, Boundary::Bounded, 0, 0)
+                .into_iter()
+                .map(|step| step.delta)
+                .collect();
+
+            Word {
+                text: text.to_string(),
+                chars,
+                steps,
+            }
+        })
+        .collect()
+}
+
+// A direction to read a word in
+#[derive(Debug, Copy, Clone)]
+struct Step {
+    delta: (isize, isize),
+    // Set for a palindrome that reads the same cells backwards in this very direction, which
+    // happens when the grid wraps after two cells. Only the reading from the lower start counts.
+    self_inverse: bool,
+}
+
+impl Step {
+    fn accepts(&self, start: (usize, usize), end: (usize, usize)) -> bool {
+        !self.self_inverse || start < end
+    }
+}
+
+// The directions to try `word` in so that every occurrence is found exactly once.
+//
+// Directions that take the same path on a wrapping grid are only tried once, a palindrome is
+// only tried in one of each pair of opposite directions, and a word that would need to use a
+// cell twice isn't tried at all.
+fn plan(
+    word: &[char],
+    directions: Directions,
+    boundary: Boundary,
+    rows: usize,
+    cols: usize,
+) -> Vec<Step> {
+    let all = directions.steps();
+
+    if word.len() == 1 {
+        return vec![Step {
+            delta: all[0],
+            self_inverse: false,
+        }];
+    }
+
+    let palindrome = word.iter().eq(word.iter().rev());
+
+    let mut steps = Vec::new();
+    let mut taken = Vec::new();
+
+    for (dr, dc) in all.iter().copied() {
+        let effective = boundary.effective((dr, dc), rows, cols);
+        let reverse = boundary.effective((-dr, -dc), rows, cols);
+
+        if taken.contains(&effective) || (palindrome && taken.contains(&reverse)) {
+            continue;
+        }
+
+        if boundary
+            .period((dr, dc), rows, cols)
+            .is_some_and(|period| period < word.len())
+        {
+            continue;
+        }
+
+        taken.push(effective);
+        steps.push(Step {
+            delta: (dr, dc),
+            self_inverse: palindrome && effective == reverse,
+        });
+    }
+
+    steps
+}
+
+/// Counts occurrences of a set of words in a letter grid.
+///
+/// A palindrome read backwards is the same occurrence, so it is only searched for in one of
+/// each pair of opposite directions, and a single letter is found once per cell. On a grid
+/// that wraps, an occurrence may not use a cell twice, so a word longer than the loop it runs
+/// along isn't found.
+pub struct WordSearch {
+    words: Vec<Word>,
+    directions: Directions,
+    boundary: Boundary,
+}
+
+impl WordSearch {
+    pub fn new(words: &[&str], directions: Directions) -> Self {
+        Self::with_boundary(words, directions, Boundary::Bounded)
+    }
+
+    pub fn with_boundary(words: &[&str], directions: Directions, boundary: Boundary) -> Self {
+        Self {
+            words: prepare(words, directions),
+            directions,
+            boundary,
+        }
+    }
+
+    fn plans(&self, grid: &Grid<char>) -> Vec<Vec<Step>> {
+        self.words
+            .iter()
+            .map(|word| {
+                plan(
+                    &word.chars,
+                    self.directions,
+                    self.boundary,
+                    grid.rows(),
+                    grid.cols(),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the number of occurrences of each word.
+    pub fn count(&self, grid: &Grid<char>) -> BTreeMap<String, usize> {
+        let plans = self.plans(grid);
+        let mut counts = vec![0; self.words.len()];
+
+        for row in 0..grid.rows() {
+            add(&mut counts, self.count_row(grid, &plans, row));
+        }
+
+        self.with_text(counts)
+    }
+
+    /// Same as `count`, with the rows of the grid split across rayon's thread pool.
+    pub fn par_count(&self, grid: &Grid<char>) -> BTreeMap<String, usize> {
+        let plans = self.plans(grid);
+        let counts = (0..grid.rows())
+            .into_par_iter()
+            .map(|row| self.count_row(grid, &plans, row))
+            .reduce(
+                || vec![0; self.words.len()],
+                |mut a, b| {
+                    add(&mut a, b);
+                    a
+                },
+            );
+
+        self.with_text(counts)
+    }
+
+    // Occurrences of each word that start in `row`
+    fn count_row(&self, grid: &Grid<char>, plans: &[Vec<Step>], row: usize) -> Vec<usize> {
+        self.words
+            .iter()
+            .zip(plans)
+            .map(|(word, steps)| {
+                (0..grid.cols())
+                    .map(|col| {
+                        steps
+                            .iter()
+                            .filter(|step| {
+                                self.find_word(grid, (row, col), step.delta, &word.chars)
+                                    .is_some_and(|end| step.accepts((row, col), end))
+                            })
+                            .count()
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+
+    fn with_text(&self, counts: Vec<usize>) -> BTreeMap<String, usize> {
+        self.words
+            .iter()
+            .zip(counts)
+            .map(|(word, count)| (word.text.clone(), count))
+            .collect()
+    }
+
+    /// Returns every occurrence, grouped by word and then in reading order of the start cell.
+    pub fn matches(&self, grid: &Grid<char>) -> Vec<Match> {
+        let mut matches = Vec::new();
+
+        for (word, steps) in self.words.iter().zip(self.plans(grid)) {
+            for row in 0..grid.rows() {
+                matches.extend(self.matches_in_row(grid, row, word, &steps));
+            }
+        }
+
+        matches
+    }
+
+    /// Same as `matches`, in the same order, with the rows of the grid split across rayon's
+    /// thread pool.
+    pub fn par_matches(&self, grid: &Grid<char>) -> Vec<Match> {
+        let mut matches = Vec::new();
+
+        for (word, steps) in self.words.iter().zip(self.plans(grid)) {
+            matches.par_extend(
+                (0..grid.rows())
+                    .into_par_iter()
+                    .flat_map_iter(|row| self.matches_in_row(grid, row, word, &steps)),
+            );
+        }
+
+        matches
+    }
+
+    fn matches_in_row(
+        &self,
+        grid: &Grid<char>,
+        row: usize,
+        word: &Word,
+        steps: &[Step],
+    ) -> Vec<Match> {
+        let mut matches = Vec::new();
+
+        for col in 0..grid.cols() {
+            let pos = (row, col);
+
+            for step in steps {
+                match self.find_word(grid, pos, step.delta, &word.chars) {
+                    Some(end) if step.accepts(pos, end) => {}
+                    _ => continue,
+                }
+
+                let mut cells = vec![pos];
+                while cells.len() < word.chars.len() {
+                    let last = *cells.last().unwrap();
+                    cells.push(self.boundary.offset(grid, last, step.delta).unwrap());
+                }
+
+                matches.push(Match {
+                    word: word.text.clone(),
+                    start: pos,
+                    direction: step.delta,
+                    cells,
+                });
+            }
+        }
+
+        matches
+    }
+
+    // Returns the last cell of the word if it starts at `pos`
+    fn find_word(
+        &self,
+        grid: &Grid<char>,
+        pos: (usize, usize),
+        direction: (isize, isize),
+        word: &[char],
+    ) -> Option<(usize, usize)> {
+        if grid[pos] != word[0] {
+            return None;
+        }
+
+        if word.len() == 1 {
+            return Some(pos);
+        }
+
+        let next = self.boundary.offset(grid, pos, direction)?;
+        self.find_word(grid, next, direction, &word[1..])
+    }
+}
+
+fn add(counts: &mut [usize], row: Vec<usize>) {
+    for (count, n) in counts.iter_mut().zip(row) {
+        *count += n;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::EXAMPLE;
+    use crate::grid::load;
+
+    fn count(input: &str, words: &[&str], directions: Directions) -> BTreeMap<String, usize> {
+        WordSearch::new(words, directions).count(&load(input).unwrap())
+    }
+
+    #[test]
+    fn test_example() {
+        assert_eq!(count(EXAMPLE, &["XMAS"], Directions::All)["XMAS"], 18);
+    }
+
+    #[test]
+    fn test_per_word_counts() {
+        let counts = count(
+            EXAMPLE,
+            &["XMAS", "SAMX", "MAS", "XMAS", "QQ"],
+            Directions::All,
+        );
+
+        // A word and its reversal are found in opposite directions
+        assert_eq!(counts["XMAS"], 18);
+        assert_eq!(counts["SAMX"], 18);
+        assert_eq!(counts["QQ"], 0);
+        assert_eq!(counts.len(), 4);
+    }
+
+    #[test]
+    fn test_direction_sets() {
+        let orthogonal = count(EXAMPLE, &["XMAS"], Directions::Orthogonal)["XMAS"];
+        let diagonal = count(EXAMPLE, &["XMAS"], Directions::Diagonal)["XMAS"];
+
+        assert_eq!(orthogonal, 8);
+        assert_eq!(diagonal, 10);
+        assert_eq!(orthogonal + diagonal, 18);
+    }
+
+    #[test]
+    fn test_palindromes() {
+        let grid = "ABA\nBBB\nABA";
+
+        // Each row, column and diagonal, read either way
+        assert_eq!(count(grid, &["ABA"], Directions::Orthogonal)["ABA"], 4);
+        assert_eq!(count(grid, &["ABA"], Directions::All)["ABA"], 6);
+        assert_eq!(count(grid, &["BBB"], Directions::All)["BBB"], 2);
+        assert_eq!(count(grid, &["AB"], Directions::All)["AB"], 12);
+        assert_eq!(count(grid, &["BB"], Directions::All)["BB"], 8);
+        assert_eq!(count(grid, &["AA"], Directions::All)["AA"], 0);
+    }
+
+    #[test]
+    fn test_single_letters() {
+        let grid = "ABA\nBBB\nABA";
+
+        for directions in [
+            Directions::Orthogonal,
+            Directions::Diagonal,
+            Directions::All,
+        ] {
+            let counts = count(grid, &["A", "B"], directions);
+            assert_eq!(counts["A"], 4);
+            assert_eq!(counts["B"], 5);
+        }
+    }
+
+    #[test]
+    fn test_rectangular_grids() {
+        let wide = "XMASAMXMAS\nMQQQQQQQQA\nAQQQQQQQQM";
+        assert_eq!(count(wide, &["XMAS"], Directions::All)["XMAS"], 3);
+        assert_eq!(count(wide, &["XMA"], Directions::All)["XMA"], 4);
+
+        let tall = "XS\nMA\nAM\nSX\nQQ";
+        assert_eq!(count(tall, &["XMAS"], Directions::All)["XMAS"], 2);
+        assert_eq!(count(tall, &["XMAS"], Directions::Diagonal)["XMAS"], 0);
+
+        let line = "XMASAMXQSAMX";
+        assert_eq!(count(line, &["XMAS"], Directions::All)["XMAS"], 3);
+        assert_eq!(count(line, &["XMAS"], Directions::Diagonal)["XMAS"], 0);
+    }
+
+    #[test]
+    fn test_matches() {
+        let grid = load(EXAMPLE).unwrap();
+        let matches = WordSearch::new(&["XMAS", "A"], Directions::All).matches(&grid);
+
+        let xmas = matches
+            .iter()
+            .filter(|m| m.word == "XMAS")
+            .collect::<Vec<_>>();
+        assert_eq!(xmas.len(), 18);
+
+        assert_eq!(
+            *xmas[0],
+            Match {
+                word: "XMAS".to_string(),
+                start: (0, 4),
+                direction: (1, 1),
+                cells: vec![(0, 4), (1, 5), (2, 6), (3, 7)],
+            }
+        );
+        assert_eq!(xmas[1].start, (0, 5));
+        assert_eq!(xmas[1].direction, (0, 1));
+
+        for m in &xmas {
+            let word = m.cells.iter().map(|cell| grid[*cell]).collect::<String>();
+            assert_eq!(word, "XMAS");
+            assert_eq!(m.cells[0], m.start);
+        }
+
+        let counts = WordSearch::new(&["XMAS", "A"], Directions::All).count(&grid);
+        assert_eq!(matches.len(), counts["XMAS"] + counts["A"]);
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        use crate::generate::letter_grid;
+
+        let words = ["XMAS", "SAS", "MM", "A"];
+
+        for seed in 0..10 {
+            let grid = load(&letter_grid(seed, 37, 23, &['X', 'M', 'A', 'S'])).unwrap();
+
+            for directions in [
+                Directions::Orthogonal,
+                Directions::Diagonal,
+                Directions::All,
+            ] {
+                let search = WordSearch::new(&words, directions);
+                assert_eq!(search.par_count(&grid), search.count(&grid));
+                assert_eq!(search.par_matches(&grid), search.matches(&grid));
+            }
+        }
+    }
+
+    fn count_wrapped(input: &str, word: &str, boundary: Boundary) -> usize {
+        WordSearch::with_boundary(&[word], Directions::All, boundary).count(&load(input).unwrap())
+            [word]
+    }
+
+    #[test]
+    fn test_wrapping() {
+        let grid = "SXMA\nQQQQ\nQQQQ";
+        assert_eq!(count_wrapped(grid, "XMAS", Boundary::Bounded), 0);
+        assert_eq!(count_wrapped(grid, "XMAS", Boundary::WrapHorizontal), 1);
+        assert_eq!(count_wrapped(grid, "XMAS", Boundary::WrapVertical), 0);
+        assert_eq!(count_wrapped(grid, "XMAS", Boundary::Torus), 1);
+
+        let grid = "AQQ\nQQQ\nSQQ\nXQQ\nMQQ";
+        assert_eq!(count_wrapped(grid, "XMAS", Boundary::WrapHorizontal), 0);
+        assert_eq!(count_wrapped(grid, "XMAS", Boundary::WrapVertical), 0);
+        assert_eq!(count_wrapped(grid, "XMA", Boundary::WrapVertical), 1);
+
+        // Down and to the right from the bottom-right corner, back to the top-left
+        let grid = "AQQ\nQSQ\nQQX";
+        assert_eq!(count_wrapped(grid, "XAS", Boundary::Bounded), 0);
+        assert_eq!(count_wrapped(grid, "XAS", Boundary::Torus), 1);
+    }
+
+    #[test]
+    fn test_words_longer_than_the_grid() {
+        // Reading on around the loop would use a cell twice
+        assert_eq!(count_wrapped("ABC", "ABCAB", Boundary::Torus), 0);
+        assert_eq!(count_wrapped("ABC", "ABC", Boundary::Torus), 1);
+        assert_eq!(count_wrapped("ABC", "CAB", Boundary::WrapHorizontal), 1);
+        assert_eq!(count_wrapped("ABC", "BA", Boundary::WrapHorizontal), 1);
+
+        // On two wrapping rows, down and up lead to the same cell
+        assert_eq!(count_wrapped("A\nB", "AB", Boundary::Torus), 1);
+        assert_eq!(count_wrapped("A\nB", "AB", Boundary::Bounded), 1);
+        assert_eq!(count_wrapped("A\nA", "AA", Boundary::WrapVertical), 1);
+        assert_eq!(count_wrapped("A\nA", "AAA", Boundary::WrapVertical), 0);
+        assert_eq!(count_wrapped("AA\nAA", "AA", Boundary::Torus), 6);
+        assert_eq!(count_wrapped("A", "A", Boundary::Torus), 1);
+    }
+
+    // Every distinct run of cells spelling `word`, found the slow way
+    fn reference_count(
+        grid: &Grid<char>,
+        word: &str,
+        directions: Directions,
+        boundary: Boundary,
+    ) -> usize {
+        let chars = word.chars().collect::<Vec<_>>();
+        let mut found = Vec::new();
+
+        for start in grid.positions() {
+            for step in directions.steps() {
+                let mut cells = vec![start];
+                while cells.len() < chars.len() {
+                    match boundary.offset(grid, *cells.last().unwrap(), *step) {
+                        Some(next) => cells.push(next),
+                        None => break,
+                    }
+                }
+
+                let mut unique = cells.clone();
+                unique.sort_unstable();
+                unique.dedup();
+
+                if unique.len() < chars.len()
+                    || cells.iter().zip(&chars).any(|(cell, c)| grid[*cell] != *c)
+                {
+                    continue;
+                }
+
+                // A palindrome read backwards covers the same cells
+                let reversed = cells.iter().rev().copied().collect::<Vec<_>>();
+                found.push(cells.min(reversed));
+            }
+        }
+
+        found.sort_unstable();
+        found.dedup();
+        found.len()
+    }
+
+    #[test]
+    fn test_boundaries_match_reference() {
+        use crate::generate::letter_grid;
+        use shared::rng::Rng;
+
+        let words = ["A", "AB", "ABA", "ABBA", "AA", "AAA", "AAAA", "BAB", "ABAB"];
+        let mut rng = Rng::new(3);
+
+        for seed in 0..60 {
+            let rows = 1 + rng.below(5);
+            let cols = 1 + rng.below(5);
+            let grid = load(&letter_grid(seed, rows, cols, &['A', 'B'])).unwrap();
+
+            for boundary in [
+                Boundary::Bounded,
+                Boundary::WrapHorizontal,
+                Boundary::WrapVertical,
+                Boundary::Torus,
+            ] {
+                for directions in [
+                    Directions::Orthogonal,
+                    Directions::Diagonal,
+                    Directions::All,
+                ] {
+                    let search = WordSearch::with_boundary(&words, directions, boundary);
+                    let counts = search.count(&grid);
+
+                    for word in words {
+                        assert_eq!(
+                            counts[word],
+                            reference_count(&grid, word, directions, boundary),
+                            "{} in {}x{} grid, {:?}, {:?}",
+                            word,
+                            rows,
+                            cols,
+                            directions,
+                            boundary
+                        );
+                    }
+
+                    assert_eq!(search.par_count(&grid), counts);
+                    assert_eq!(search.matches(&grid).len(), counts.values().sum::<usize>());
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_empty_word() {
+        WordSearch::new(&[""], Directions::All);
+    }
+}
@@ -0,0 +1,348 @@
+use crate::grid::{get_dims, GridError};
+use rayon::prelude::*;
+use shared::grid::Grid;
+
+/// Stands for any letter in a template.
+pub const WILDCARD: char = '.';
+
+/// A small 2D pattern of letters and wildcards, e.g. the X-MAS cross:
+///
+/// ```text
+/// M.S
+/// .A.
+/// M.S
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    rows: usize,
+    cols: usize,
+    // `(row, col)` and letter of every cell that isn't a wildcard, sorted
+    cells: Vec<((usize, usize), char)>,
+}
+
+impl Template {
+    pub fn parse(input: &str) -> Result<Self, GridError> {
+        let (rows, cols) = get_dims(input)?;
+
+        let mut cells = Vec::new();
+        for (row, line) in input.lines().enumerate() {
+            for (col, c) in line.chars().enumerate() {
+                if c != WILDCARD {
+                    cells.push(((row, col), c));
+                }
+            }
+        }
+
+        if cells.is_empty() {
+            return Err(GridError::Empty);
+        }
+
+        Ok(Self { rows, cols, cells })
+    }
+
+    /// Turns the template a quarter turn clockwise.
+    pub fn rotate(&self) -> Self {
+        self.map_cells(self.cols, self.rows, |(row, col)| {
+            (col, self.rows - 1 - row)
+        })
+    }
+
+    /// Mirrors the template left to right.
+    pub fn reflect(&self) -> Self {
+        self.map_cells(self.rows, self.cols, |(row, col)| {
+            (row, self.cols - 1 - col)
+        })
+    }
+
+    fn map_cells(
+        &self,
+        rows: usize,
+        cols: usize,
+        f: impl Fn((usize, usize)) -> (usize, usize),
+    ) -> Self {
+        let mut cells = self
+            .cells
+            .iter()
+            .map(|(cell, c)| (f(*cell), *c))
+            .collect::<Vec<_>>();
+        cells.sort_unstable();
+
+        Self { rows, cols, cells }
+    }
+
+    fn matches_at(&self, grid: &Grid<char>, row: usize, col: usize) -> bool {
+        self.cells
+            .iter()
+            .all(|((dr, dc), c)| grid.get((row + dr, col + dc)) == Some(c))
+    }
+}
+
+/// Which orientations of a template count as occurrences.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Symmetry {
+    None,
+    Rotations,
+    RotationsAndReflections,
+}
+
+/// One occurrence of a template. `variant` indexes `PatternSearch::variants`,
+/// and `cells` are the grid cells matched by letters, not wildcards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternMatch {
+    pub origin: (usize, usize),
+    pub variant: usize,
+    pub cells: Vec<(usize, usize)>,
+}
+
+/// Finds every placement of a template in a letter grid.
+///
+/// Orientations that look the same, like the rotations of a symmetric template,
+/// are only searched for once.
+pub struct PatternSearch {
+    variants: Vec<Template>,
+}
+
+impl PatternSearch {
+    pub fn new(template: &Template, symmetry: Symmetry) -> Self {
+        let mut variants = vec![template.clone()];
+
+        if symmetry != Symmetry::None {
+            for _ in 0..3 {
+                let turned = variants.last().unwrap().rotate();
+                variants.push(turned);
+            }
+        }
+
+        if symmetry == Symmetry::RotationsAndReflections {
+            let reflected = variants.iter().map(Template::reflect).collect::<Vec<_>>();
+            variants.extend(reflected);
+        }
+
+        let mut unique: Vec<Template> = Vec::with_capacity(variants.len());
+        for variant in variants {
+            if !unique.contains(&variant) {
+                unique.push(variant);
+            }
+        }
+
+        Self { variants: unique }
+    }
+
+    pub fn variants(&self) -> &[Template] {
+        &self.variants
+    }
+
+    pub fn count(&self, grid: &Grid<char>) -> usize {
+        let mut count = 0;
+
+        for variant in &self.variants {
+            for_each_origin(variant, grid, |row, col| {
+                if variant.matches_at(grid, row, col) {
+                    count += 1;
+                }
+            });
+        }
+
+        count
+    }
+
+    /// Same as `count`, with the rows of the grid split across rayon's thread pool.
+    pub fn par_count(&self, grid: &Grid<char>) -> usize {
+        self.variants
+            .iter()
+            .filter(|variant| fits(variant, grid))
+            .map(|variant| {
+                (0..=grid.rows() - variant.rows)
+                    .into_par_iter()
+                    .map(|row| {
+                        (0..=grid.cols() - variant.cols)
+                            .filter(|col| variant.matches_at(grid, row, *col))
+                            .count()
+                    })
+                    .sum::<usize>()
+            })
+            .sum()
+    }
+
+    /// Returns every occurrence, grouped by variant and then in reading order of the origin.
+    pub fn matches(&self, grid: &Grid<char>) -> Vec<PatternMatch> {
+        let mut matches = Vec::new();
+
+        for (index, variant) in self.variants.iter().enumerate() {
+            for_each_origin(variant, grid, |row, col| {
+                if variant.matches_at(grid, row, col) {
+                    matches.push(PatternMatch {
+                        origin: (row, col),
+                        variant: index,
+                        cells: variant
+                            .cells
+                            .iter()
+                            .map(|((dr, dc), _)| (row + dr, col + dc))
+                            .collect(),
+                    });
+                }
+            });
+        }
+
+        matches
+    }
+}
+
+fn fits(template: &Template, grid: &Grid<char>) -> bool {
+    template.rows <= grid.rows() && template.cols <= grid.cols()
+}
+
+// Calls `f` with every top-left corner at which the template fits inside the grid
+fn for_each_origin(template: &Template, grid: &Grid<char>, mut f: impl FnMut(usize, usize)) {
+    if !fits(template, grid) {
+        return;
+    }
+
+    for row in 0..=grid.rows() - template.rows {
+        for col in 0..=grid.cols() - template.cols {
+            f(row, col);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::EXAMPLE;
+    use crate::grid::load;
+
+    const X_MAS: &str = "M.S\n.A.\nM.S";
+
+    fn count(input: &str, template: &str, symmetry: Symmetry) -> usize {
+        PatternSearch::new(&Template::parse(template).unwrap(), symmetry)
+            .count(&load(input).unwrap())
+    }
+
+    #[test]
+    fn test_x_mas_example() {
+        assert_eq!(count(EXAMPLE, X_MAS, Symmetry::Rotations), 9);
+        assert_eq!(count(EXAMPLE, X_MAS, Symmetry::RotationsAndReflections), 9);
+        assert_eq!(count(EXAMPLE, X_MAS, Symmetry::None), 2);
+    }
+
+    #[test]
+    fn test_parse() {
+        let template = Template::parse(X_MAS).unwrap();
+        assert_eq!((template.rows, template.cols), (3, 3));
+        assert_eq!(template.cells.len(), 5);
+        assert_eq!(template.cells[2], ((1, 1), 'A'));
+
+        assert_eq!(Template::parse("...\n..."), Err(GridError::Empty));
+        assert!(Template::parse("M.S\n.A").is_err());
+    }
+
+    #[test]
+    fn test_rotate_and_reflect() {
+        let template = Template::parse("AB\n.C").unwrap();
+
+        assert_eq!(template.rotate(), Template::parse(".A\nCB").unwrap());
+        assert_eq!(template.reflect(), Template::parse("BA\nC.").unwrap());
+        assert_eq!(template.rotate().rotate().rotate().rotate(), template);
+
+        let wide = Template::parse("ABC").unwrap();
+        assert_eq!(wide.rotate(), Template::parse("A\nB\nC").unwrap());
+    }
+
+    #[test]
+    fn test_symmetric_templates_are_not_double_counted() {
+        let template = |t: &str, symmetry| {
+            PatternSearch::new(&Template::parse(t).unwrap(), symmetry)
+                .variants()
+                .len()
+        };
+
+        assert_eq!(template(X_MAS, Symmetry::RotationsAndReflections), 4);
+        assert_eq!(
+            template("A.A\n.A.\nA.A", Symmetry::RotationsAndReflections),
+            1
+        );
+        assert_eq!(template("AB", Symmetry::RotationsAndReflections), 4);
+        assert_eq!(template("AB\n.C", Symmetry::Rotations), 4);
+        assert_eq!(template("AB\n.C", Symmetry::RotationsAndReflections), 8);
+
+        assert_eq!(
+            count("AAA\nAAA\nAAA", "A.A\n.A.\nA.A", Symmetry::Rotations),
+            1
+        );
+    }
+
+    #[test]
+    fn test_reflections_are_optional() {
+        // A mirror image of "AB/.C" that no rotation produces
+        let grid = "BAQ\nCQQ";
+
+        assert_eq!(count(grid, "AB\n.C", Symmetry::None), 0);
+        assert_eq!(count(grid, "AB\n.C", Symmetry::Rotations), 0);
+        assert_eq!(count(grid, "AB\n.C", Symmetry::RotationsAndReflections), 1);
+    }
+
+    #[test]
+    fn test_wildcards_match_anything() {
+        assert_eq!(count("AXB\nAYB\nAZB", "A.B", Symmetry::None), 3);
+        assert_eq!(count("AXB\nYQZ\nBWA", "A.B", Symmetry::None), 1);
+        assert_eq!(count("AXB\nYQZ\nBWA", "A.B", Symmetry::Rotations), 4);
+    }
+
+    #[test]
+    fn test_template_bigger_than_grid() {
+        assert_eq!(count("MAS", X_MAS, Symmetry::Rotations), 0);
+        assert_eq!(count("M\nA\nS", "MAS", Symmetry::None), 0);
+        assert_eq!(count("M\nA\nS", "MAS", Symmetry::Rotations), 1);
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        use crate::generate::letter_grid;
+
+        let grid = load(&letter_grid(5, 41, 29, &['M', 'A', 'S'])).unwrap();
+
+        for template in [X_MAS, "AB\n.C", "MAS", "M\nA\nS\nM\nA\nS\nM"] {
+            let template = Template::parse(template).unwrap();
+            for symmetry in [
+                Symmetry::None,
+                Symmetry::Rotations,
+                Symmetry::RotationsAndReflections,
+            ] {
+                let search = PatternSearch::new(&template, symmetry);
+                assert_eq!(search.par_count(&grid), search.count(&grid));
+            }
+        }
+
+        let wide = Template::parse(&"M".repeat(30)).unwrap();
+        assert_eq!(
+            PatternSearch::new(&wide, Symmetry::None).par_count(&grid),
+            0
+        );
+    }
+
+    #[test]
+    fn test_matches() {
+        let grid = load(EXAMPLE).unwrap();
+        let search = PatternSearch::new(&Template::parse(X_MAS).unwrap(), Symmetry::Rotations);
+        let matches = search.matches(&grid);
+
+        assert_eq!(matches.len(), 9);
+
+        for m in &matches {
+            let variant = &search.variants()[m.variant];
+            for (cell, (_, c)) in m.cells.iter().zip(&variant.cells) {
+                assert_eq!(grid[*cell], *c);
+            }
+            assert_eq!(m.cells.len(), 5);
+        }
+
+        // The top-left X-MAS from the puzzle description, with both M's on the left
+        assert_eq!(
+            matches
+                .iter()
+                .find(|m| m.origin == (0, 1))
+                .map(|m| m.cells.clone()),
+            Some(vec![(0, 1), (0, 3), (1, 2), (2, 1), (2, 3)])
+        );
+    }
+}
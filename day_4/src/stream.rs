@@ -0,0 +1,279 @@
+use crate::grid::GridError;
+use crate::search::{prepare, Directions, Word};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::io::{self, BufRead};
+
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    Grid(GridError),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::Io(err) => write!(f, "{}", err),
+            StreamError::Grid(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+impl From<io::Error> for StreamError {
+    fn from(err: io::Error) -> Self {
+        StreamError::Io(err)
+    }
+}
+
+impl From<GridError> for StreamError {
+    fn from(err: GridError) -> Self {
+        StreamError::Grid(err)
+    }
+}
+
+/// Counts the same occurrences as `WordSearch` on a bounded grid, reading the grid a line at a
+/// time so that only as many rows as the longest word are held in memory.
+///
+/// Every occurrence is counted from its top row. A word read upwards starts further down, so
+/// the window always reaches the bottom of any occurrence that starts or ends in its top row.
+pub struct StreamSearch {
+    words: Vec<Word>,
+    // Rows kept in the window, the length of the longest word
+    height: usize,
+}
+
+impl StreamSearch {
+    pub fn new(words: &[&str], directions: Directions) -> Self {
+        let words = prepare(words, directions);
+        let height = words.iter().map(|word| word.chars.len()).max().unwrap_or(1);
+
+        Self { words, height }
+    }
+
+    /// Returns the number of occurrences of each word in the grid read from `reader`, which
+    /// must be rectangular like a grid passed to `load`.
+    pub fn count(&self, reader: impl BufRead) -> Result<BTreeMap<String, usize>, StreamError> {
+        let mut counts = vec![0; self.words.len()];
+        let mut window: VecDeque<Vec<char>> = VecDeque::with_capacity(self.height);
+        let mut cols = None;
+
+        for (index, line) in reader.lines().enumerate() {
+            let row = line?.chars().collect::<Vec<_>>();
+
+            let expected = *cols.get_or_insert(row.len());
+            if row.len() != expected {
+                return Err(GridError::Ragged {
+                    line: index + 1,
+                    expected,
+                    found: row.len(),
+                }
+                .into());
+            }
+
+            window.push_back(row);
+
+            if window.len() == self.height {
+                self.count_top_row(&window, &mut counts);
+                window.pop_front();
+            }
+        }
+
+        if cols.unwrap_or(0) == 0 {
+            return Err(GridError::Empty.into());
+        }
+
+        // The last rows, with nothing more below them
+        while !window.is_empty() {
+            self.count_top_row(&window, &mut counts);
+            window.pop_front();
+        }
+
+        Ok(self
+            .words
+            .iter()
+            .zip(counts)
+            .map(|(word, count)| (word.text.clone(), count))
+            .collect())
+    }
+
+    // Occurrences of each word whose highest cell is in the first row of the window
+    fn count_top_row(&self, window: &VecDeque<Vec<char>>, counts: &mut [usize]) {
+        for (word, count) in self.words.iter().zip(counts) {
+            for col in 0..window[0].len() {
+                for (dr, dc) in word.steps.iter().copied() {
+                    // Read upwards, the word ends in the top row
+                    let row = if dr < 0 { word.chars.len() - 1 } else { 0 };
+
+                    if find_word(window, (row, col), (dr, dc), &word.chars) {
+                        *count += 1;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn find_word(
+    window: &VecDeque<Vec<char>>,
+    (row, col): (usize, usize),
+    (dr, dc): (isize, isize),
+    word: &[char],
+) -> bool {
+    if window.get(row).and_then(|cells| cells.get(col)) != Some(&word[0]) {
+        return false;
+    }
+
+    if word.len() == 1 {
+        return true;
+    }
+
+    match (row.checked_add_signed(dr), col.checked_add_signed(dc)) {
+        (Some(row), Some(col)) => find_word(window, (row, col), (dr, dc), &word[1..]),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::EXAMPLE;
+    use crate::generate::letter_grid;
+    use crate::grid::load;
+    use crate::search::WordSearch;
+    use shared::rng::Rng;
+    use std::io::Read;
+
+    #[test]
+    fn test_example() {
+        let counts = StreamSearch::new(&["XMAS", "MAS", "A"], Directions::All)
+            .count(EXAMPLE.as_bytes())
+            .unwrap();
+
+        assert_eq!(counts["XMAS"], 18);
+        assert_eq!(
+            counts,
+            WordSearch::new(&["XMAS", "MAS", "A"], Directions::All).count(&load(EXAMPLE).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_matches_word_search() {
+        let alphabet = ['A', 'B', 'C'];
+        let words = [
+            "A", "AB", "ABA", "ABC", "CBAAB", "ACCA", "BBBBBB", "ABCABCAB",
+        ];
+        let mut rng = Rng::new(5);
+
+        for seed in 0..40 {
+            let rows = 1 + rng.below(15);
+            let cols = 1 + rng.below(8);
+            let input = letter_grid(seed, rows, cols, &alphabet);
+
+            for directions in [
+                Directions::Orthogonal,
+                Directions::Diagonal,
+                Directions::All,
+            ] {
+                assert_eq!(
+                    StreamSearch::new(&words, directions)
+                        .count(input.as_bytes())
+                        .unwrap(),
+                    WordSearch::new(&words, directions).count(&load(&input).unwrap()),
+                    "{}x{} grid, {:?}",
+                    rows,
+                    cols,
+                    directions
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_crossing_the_window() {
+        // Vertical and diagonal words read both ways, across every boundary between windows
+        let input = "SQQS\nAQAQ\nMMQQ\nXQQX\nQQQM\nQQQA\nXQQS";
+        let counts = StreamSearch::new(&["XMAS"], Directions::All)
+            .count(input.as_bytes())
+            .unwrap();
+
+        assert_eq!(counts["XMAS"], 3);
+        assert_eq!(
+            counts,
+            WordSearch::new(&["XMAS"], Directions::All).count(&load(input).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let search = StreamSearch::new(&["XMAS"], Directions::All);
+
+        assert!(matches!(
+            search.count("XMAS\nXMA\nXMAS".as_bytes()),
+            Err(StreamError::Grid(GridError::Ragged {
+                line: 2,
+                expected: 4,
+                found: 3,
+            }))
+        ));
+        assert!(matches!(
+            search.count("".as_bytes()),
+            Err(StreamError::Grid(GridError::Empty))
+        ));
+        assert_eq!(search.count("XM\r\nAS\r\n".as_bytes()).unwrap()["XMAS"], 0);
+    }
+
+    // Rows of a grid that is never held in memory all at once
+    struct TallGrid {
+        rng: Rng,
+        rows: usize,
+        cols: usize,
+        line: Vec<u8>,
+        pos: usize,
+    }
+
+    impl Read for TallGrid {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.pos == self.line.len() {
+                if self.rows == 0 {
+                    return Ok(0);
+                }
+                self.rows -= 1;
+                self.line = (0..self.cols)
+                    .map(|_| self.rng.pick(b"XMAS"))
+                    .chain([b'\n'])
+                    .collect();
+                self.pos = 0;
+            }
+
+            let n = buf.len().min(self.line.len() - self.pos);
+            buf[..n].copy_from_slice(&self.line[self.pos..self.pos + n]);
+            self.pos += n;
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_tall_grid() {
+        let grid = || TallGrid {
+            rng: Rng::new(1),
+            rows: 200_000,
+            cols: 8,
+            line: Vec::new(),
+            pos: 0,
+        };
+
+        let counts = StreamSearch::new(&["XMAS"], Directions::All)
+            .count(io::BufReader::new(grid()))
+            .unwrap();
+
+        let mut input = String::new();
+        grid().read_to_string(&mut input).unwrap();
+        assert_eq!(
+            counts,
+            WordSearch::new(&["XMAS"], Directions::All).count(&load(&input).unwrap())
+        );
+        assert!(counts["XMAS"] > 0);
+    }
+}
@@ -0,0 +1,264 @@
+use crate::order::{topological_order, OrderError};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+/// A set of pages whose rules lead from each of them to all the others, and so can't all be
+/// put in order. `cycle` is one of the shortest cycles through the smallest page, starting
+/// and ending with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Component {
+    pub pages: Vec<i32>,
+    pub cycle: Vec<i32>,
+}
+
+/// Returns the strongly connected components of the rule graph that contain a cycle, sorted by
+/// their smallest page.
+pub fn cyclic_components(lookup: &BTreeMap<i32, Vec<i32>>) -> Vec<Component> {
+    let mut tarjan = Tarjan {
+        lookup,
+        index: BTreeMap::new(),
+        low: BTreeMap::new(),
+        stack: Vec::new(),
+        on_stack: BTreeSet::new(),
+        components: Vec::new(),
+    };
+
+    for page in pages(lookup) {
+        if !tarjan.index.contains_key(&page) {
+            tarjan.visit(page);
+        }
+    }
+
+    let mut components = tarjan
+        .components
+        .into_iter()
+        .filter(|pages| pages.len() > 1 || follows(lookup, pages[0], pages[0]))
+        .map(|mut pages| {
+            pages.sort_unstable();
+            let cycle = shortest_cycle(lookup, &pages);
+            Component { pages, cycle }
+        })
+        .collect::<Vec<_>>();
+
+    components.sort_by_key(|component| component.pages[0]);
+    components
+}
+
+// Every page named by a rule, on either side
+fn pages(lookup: &BTreeMap<i32, Vec<i32>>) -> BTreeSet<i32> {
+    lookup
+        .iter()
+        .flat_map(|(left, rights)| std::iter::once(left).chain(rights))
+        .copied()
+        .collect()
+}
+
+fn follows(lookup: &BTreeMap<i32, Vec<i32>>, left: i32, right: i32) -> bool {
+    lookup
+        .get(&left)
+        .is_some_and(|rights| rights.contains(&right))
+}
+
+struct Tarjan<'a> {
+    lookup: &'a BTreeMap<i32, Vec<i32>>,
+    index: BTreeMap<i32, usize>,
+    low: BTreeMap<i32, usize>,
+    stack: Vec<i32>,
+    on_stack: BTreeSet<i32>,
+    components: Vec<Vec<i32>>,
+}
+
+impl Tarjan<'_> {
+    fn visit(&mut self, page: i32) {
+        let index = self.index.len();
+        self.index.insert(page, index);
+        self.low.insert(page, index);
+        self.stack.push(page);
+        self.on_stack.insert(page);
+
+        for next in self.lookup.get(&page).into_iter().flatten().copied() {
+            if !self.index.contains_key(&next) {
+                self.visit(next);
+                let low = self.low[&page].min(self.low[&next]);
+                self.low.insert(page, low);
+            } else if self.on_stack.contains(&next) {
+                let low = self.low[&page].min(self.index[&next]);
+                self.low.insert(page, low);
+            }
+        }
+
+        if self.low[&page] == self.index[&page] {
+            let mut component = Vec::new();
+            while let Some(top) = self.stack.pop() {
+                self.on_stack.remove(&top);
+                component.push(top);
+                if top == page {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
+
+// Breadth first from the smallest page back to itself, staying inside the component
+fn shortest_cycle(lookup: &BTreeMap<i32, Vec<i32>>, pages: &[i32]) -> Vec<i32> {
+    let start = pages[0];
+    let mut previous = BTreeMap::new();
+    let mut queue = VecDeque::from([start]);
+
+    while let Some(page) = queue.pop_front() {
+        for next in lookup.get(&page).into_iter().flatten().copied() {
+            if next == start {
+                let mut cycle = vec![page];
+                while let Some(before) = previous.get(cycle.last().unwrap()) {
+                    cycle.push(*before);
+                }
+                cycle.reverse();
+                cycle.push(start);
+                return cycle;
+            }
+
+            if pages.binary_search(&next).is_ok() && !previous.contains_key(&next) {
+                previous.insert(next, page);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    unreachable!("{} is on a cycle in its component", start)
+}
+
+/// An update that has no single correct order, with the rules that are to blame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateReport {
+    /// Position of the update in the input, from 0.
+    pub index: usize,
+    pub error: OrderError,
+    /// For a cycle, the rules between its pages. For two pages that could go either way,
+    /// the rules in the update that involve either of them. For a repeated page, which only
+    /// updates built by hand can have, none.
+    pub rules: Vec<(i32, i32)>,
+}
+
+/// Checks that the rules between the pages of every update put them in a single order.
+pub fn check_updates(lookup: &BTreeMap<i32, Vec<i32>>, updates: &[Vec<i32>]) -> Vec<UpdateReport> {
+    updates
+        .iter()
+        .enumerate()
+        .filter_map(|(index, update)| {
+            let error = topological_order(lookup, update).err()?;
+
+            let involved = |left: &i32, right: &i32| match &error {
+                OrderError::Repeated(_) => false,
+                OrderError::Ambiguous(a, b) => {
+                    [a, b].iter().any(|page| *page == left || *page == right)
+                }
+                OrderError::Contradictory(pages) => pages.contains(left) && pages.contains(right),
+            };
+
+            let mut rules = Vec::new();
+            for left in update {
+                for right in lookup.get(left).into_iter().flatten() {
+                    if update.contains(right) && involved(left, right) {
+                        rules.push((*left, *right));
+                    }
+                }
+            }
+            rules.sort_unstable();
+            rules.dedup();
+
+            Some(UpdateReport {
+                index,
+                error,
+                rules,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::lookup;
+
+    #[test]
+    fn test_acyclic() {
+        let lookup = lookup("47|53\n97|13\n97|61\n97|47\n75|29\n61|13\n75|53\n29|13");
+        assert!(cyclic_components(&lookup).is_empty());
+    }
+
+    #[test]
+    fn test_components() {
+        // Two cycles joined one way, a self-rule, and a page outside any cycle
+        let lookup = lookup("1|2\n2|3\n3|1\n3|4\n4|5\n5|6\n6|4\n1|3\n7|7\n8|1");
+        let components = cyclic_components(&lookup);
+
+        assert_eq!(
+            components,
+            [
+                Component {
+                    pages: vec![1, 2, 3],
+                    cycle: vec![1, 3, 1],
+                },
+                Component {
+                    pages: vec![4, 5, 6],
+                    cycle: vec![4, 5, 6, 4],
+                },
+                Component {
+                    pages: vec![7],
+                    cycle: vec![7, 7],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_full_graph_is_cyclic_but_updates_are_not() {
+        // Rock, paper, scissors: no update holds all three pages
+        let lookup = lookup("1|2\n2|3\n3|1");
+        assert_eq!(cyclic_components(&lookup).len(), 1);
+
+        let updates = vec![vec![1, 2], vec![2, 3], vec![3, 1]];
+        assert!(check_updates(&lookup, &updates).is_empty());
+
+        let updates = vec![vec![1, 2], vec![2, 1, 3]];
+        assert_eq!(
+            check_updates(&lookup, &updates),
+            [UpdateReport {
+                index: 1,
+                error: OrderError::Contradictory(vec![2, 1, 3]),
+                rules: vec![(1, 2), (2, 3), (3, 1)],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_updates() {
+        let lookup = lookup("1|2\n1|3\n3|4\n5|6");
+        let updates = vec![vec![1, 2, 3, 4], vec![1, 3, 4]];
+
+        assert_eq!(
+            check_updates(&lookup, &updates),
+            [UpdateReport {
+                index: 0,
+                error: OrderError::Ambiguous(2, 3),
+                rules: vec![(1, 2), (1, 3), (3, 4)],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_repeated_page() {
+        let lookup = lookup("5|6");
+        let updates = vec![vec![5, 6], vec![5, 6, 6]];
+
+        assert_eq!(
+            check_updates(&lookup, &updates),
+            [UpdateReport {
+                index: 1,
+                error: OrderError::Repeated(6),
+                rules: vec![],
+            }]
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use crate::order::topological_order;
+use crate::validate::violations;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// What to draw on top of an update's rules, see `update_to_dot`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Highlight {
+    /// Colour the rules the update breaks red.
+    pub violations: bool,
+    /// Join the pages in their corrected order with a dashed blue chain.
+    pub order: bool,
+}
+
+/// Writes every rule as an edge of a Graphviz digraph, from the page that comes first.
+pub fn rules_to_dot(lookup: &BTreeMap<i32, Vec<i32>>) -> String {
+    let mut dot = String::from("digraph rules {\n");
+
+    for (left, rights) in lookup {
+        for right in rights {
+            writeln!(dot, "    {} -> {};", left, right).unwrap();
+        }
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+/// Writes the pages of `update` and the rules between them as a Graphviz digraph. Pages are
+/// labelled with their position in the update, from 1.
+pub fn update_to_dot(
+    lookup: &BTreeMap<i32, Vec<i32>>,
+    update: &[i32],
+    highlight: Highlight,
+) -> String {
+    let mut dot = String::from("digraph update {\n");
+
+    for (i, page) in update.iter().enumerate() {
+        writeln!(dot, "    {} [label=\"{}\\n#{}\"];", page, page, i + 1).unwrap();
+    }
+
+    let broken = if highlight.violations {
+        violations(lookup, update)
+            .into_iter()
+            .map(|violation| violation.rule)
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    for left in update {
+        for right in lookup.get(left).into_iter().flatten() {
+            if !update.contains(right) {
+                continue;
+            }
+
+            if broken.contains(&(*left, *right)) {
+                writeln!(dot, "    {} -> {} [color=red];", left, right).unwrap();
+            } else {
+                writeln!(dot, "    {} -> {};", left, right).unwrap();
+            }
+        }
+    }
+
+    if highlight.order {
+        match topological_order(lookup, update) {
+            Ok(order) => {
+                for pair in order.windows(2) {
+                    writeln!(
+                        dot,
+                        "    {} -> {} [color=blue, style=dashed, constraint=false];",
+                        pair[0], pair[1]
+                    )
+                    .unwrap();
+                }
+            }
+            Err(err) => writeln!(dot, "    // no corrected order: {}", err).unwrap(),
+        }
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::lookup;
+
+    #[test]
+    fn test_rules() {
+        assert_eq!(
+            rules_to_dot(&lookup("47|53\n97|13\n47|13")),
+            "digraph rules {\n    47 -> 53;\n    47 -> 13;\n    97 -> 13;\n}\n"
+        );
+        assert_eq!(rules_to_dot(&BTreeMap::new()), "digraph rules {\n}\n");
+    }
+
+    #[test]
+    fn test_update() {
+        let lookup = lookup("61|13\n61|29\n29|13\n97|13");
+
+        let plain = update_to_dot(&lookup, &[61, 13, 29], Highlight::default());
+        assert_eq!(
+            plain,
+            "digraph update {\n    61 [label=\"61\\n#1\"];\n    13 [label=\"13\\n#2\"];\n    29 [label=\"29\\n#3\"];\n    61 -> 13;\n    61 -> 29;\n    29 -> 13;\n}\n"
+        );
+
+        let highlighted = update_to_dot(
+            &lookup,
+            &[61, 13, 29],
+            Highlight {
+                violations: true,
+                order: true,
+            },
+        );
+        assert!(highlighted.contains("    29 -> 13 [color=red];\n"));
+        assert!(highlighted.contains("    61 -> 13;\n"));
+        assert!(
+            highlighted.contains("    61 -> 29 [color=blue, style=dashed, constraint=false];\n")
+        );
+        assert!(
+            highlighted.contains("    29 -> 13 [color=blue, style=dashed, constraint=false];\n")
+        );
+        assert!(!highlighted.contains("97"));
+    }
+
+    #[test]
+    fn test_update_without_an_order() {
+        let dot = update_to_dot(
+            &lookup("1|2\n2|1"),
+            &[1, 2],
+            Highlight {
+                violations: false,
+                order: true,
+            },
+        );
+
+        assert!(dot.contains("    // no corrected order: the rules between 1, 2 form a cycle\n"));
+        assert!(!dot.contains("color"));
+    }
+}
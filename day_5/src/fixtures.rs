@@ -0,0 +1,34 @@
+use std::collections::BTreeMap;
+
+// The example from the puzzle. The first three updates are in order and the rest aren't.
+pub const EXAMPLE: &str = "47|53\n97|13\n97|61\n97|47\n75|29\n61|13\n75|53\n29|13\n97|29\n53|29\n61|53\n97|53\n61|29\n47|13\n75|47\n97|75\n47|61\n75|61\n47|29\n75|13\n53|13\n\n75,47,61,53,29\n97,61,53,29,13\n75,29,13\n75,97,47,61,53\n61,13,29\n97,13,75,29,47\n";
+
+// The lookup for rules given one `X|Y` per line
+pub fn lookup(rules: &str) -> BTreeMap<i32, Vec<i32>> {
+    let mut lookup: BTreeMap<i32, Vec<i32>> = BTreeMap::new();
+    for rule in rules.lines() {
+        let (left, right) = rule.split_once('|').unwrap();
+        lookup
+            .entry(left.parse().unwrap())
+            .or_default()
+            .push(right.parse().unwrap());
+    }
+    lookup
+}
+
+// The example's lookup and updates
+pub fn example() -> (BTreeMap<i32, Vec<i32>>, Vec<Vec<i32>>) {
+    let (rules, updates) = EXAMPLE.split_once("\n\n").unwrap();
+
+    let updates = updates
+        .lines()
+        .map(|update| {
+            update
+                .split(',')
+                .map(|page| page.parse().unwrap())
+                .collect()
+        })
+        .collect();
+
+    (lookup(rules), updates)
+}
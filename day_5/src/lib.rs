@@ -0,0 +1,9 @@
+pub mod analysis;
+pub mod dot;
+#[cfg(test)]
+mod fixtures;
+pub mod order;
+pub mod parse;
+pub mod repair;
+pub mod rules;
+pub mod validate;
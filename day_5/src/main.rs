@@ -1,118 +1,340 @@
-use std::cmp::Ordering::{Equal, Greater, Less};
-use std::collections::BTreeMap;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use day_5::analysis::{check_updates, cyclic_components};
+use day_5::dot::{rules_to_dot, update_to_dot, Highlight};
+use day_5::order::{topological_order, OrderError};
+use day_5::parse::parse;
+use day_5::repair::repair;
+use day_5::rules::RuleSet;
+use day_5::validate::validate;
+use std::collections::{BTreeMap, BTreeSet};
 
 fn main() {
     // Part 1 Approach:
-    // Populate a lookup table. The right part of the rule will map to all matching left parts.
-    // I.E entry 53 will contain all page numbers that must be printed before page 53.
+    // Populate a lookup table. The left part of the rule will map to all matching right parts.
+    // I.E entry 53 will contain all page numbers that must be printed after page 53.
     //
-    // Iterate through each update, and maintain a list of visited numbers.
-    // If any part of the entry for the current number is in visited, then the update is invalid.
-    // If the end is reached without triggering the above condition, then the update is valid.
+    // Iterate through each update, and look up the rules of each page.
+    // If a page that must come after the current one was already printed, the update is invalid.
+    // Otherwise the update is valid.
     //
 
     // Part 2 Approach:
     // Modified the function from part 1 to collect and return all invalid updates.
-    // Sorted with a custom comparison function using the lookup table from part 1.
+    // Each one is put in order with a topological sort of the rules between its pages.
     //
 
-    let Ok(file) = File::open("input") else {
+    let Ok(input) = std::fs::read_to_string("input") else {
         panic!("Could not open input file")
     };
 
-    let mut lookup: BTreeMap<i32, Vec<i32>> = BTreeMap::new();
-    let mut updates: Vec<Vec<i32>> = Vec::new();
+    let input = match parse(&input) {
+        Ok(input) => input,
+        Err(err) => {
+            eprintln!("Invalid input: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    for warning in &input.warnings {
+        eprintln!("Warning: {}", warning);
+    }
+
+    let (lookup, updates) = (input.lookup, input.updates);
+
+    // `--analyze` checks the rules for cycles and `--explain` lists the rules each invalid
+    // update breaks, instead of solving the puzzle.
+    // `--dot` prints the rules as a Graphviz graph, or with an update number, the rules between
+    // that update's pages with the broken ones in red and the corrected order as a chain.
+    // `--query` answers a question about the rules, or one per line of stdin, see `query`.
+    // `--repair` lists the fewest pages to move to put each invalid update in order.
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match args.first().map(|arg| arg.as_str()) {
+        Some("--analyze") => return analyze(&lookup, &updates),
+        Some("--explain") => return explain(&lookup, &updates),
+        Some("--dot") => return dot(&lookup, &updates, args.get(1)),
+        Some("--query") => return queries(&lookup, &updates, &args[1..]),
+        Some("--repair") => return repairs(&lookup, &updates),
+        _ => {}
+    }
 
-    parse_input(&file, &mut lookup, &mut updates);
     let (sum, mut invalid_updates) = get_sum(&lookup, &updates);
 
     println!("Sum of middle pages: {}", sum);
 
-    let sum_2 = sort_and_get_sum(&lookup, &mut invalid_updates);
+    match sort_and_get_sum(&lookup, &mut invalid_updates) {
+        Ok(sum_2) => println!("Sum of sorted middle pages: {}", sum_2),
+        Err((update, err)) => {
+            eprintln!("Cannot sort update {:?}: {}", update, err);
+            std::process::exit(1);
+        }
+    }
+}
 
-    println!("Sum of sorted middle pages: {}", sum_2);
+fn join<T: ToString>(items: impl IntoIterator<Item = T>, separator: &str) -> String {
+    items
+        .into_iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(separator)
 }
 
-fn parse_input(input: &File, lookup: &mut BTreeMap<i32, Vec<i32>>, updates: &mut Vec<Vec<i32>>) {
-    let reader = BufReader::new(input);
+fn analyze(lookup: &BTreeMap<i32, Vec<i32>>, updates: &[Vec<i32>]) {
+    let components = cyclic_components(lookup);
 
-    reader.lines().map_while(|line| line.ok()).for_each(|line| {
-        if line.contains(',') {
-            let update = line
-                .split(',')
-                .filter_map(|s| s.parse::<i32>().ok())
-                .collect::<Vec<i32>>();
-            updates.push(update);
-        } else if line.contains('|') {
-            let mut split = line.split('|').filter_map(|s| s.parse::<i32>().ok());
-            let left = split.next().expect("Invalid rule encountered");
-            let right = split.last().expect("Invalid rule encountered");
-            lookup
-                .entry(left)
-                .and_modify(|entry| entry.push(right))
-                .or_insert(vec![right]);
+    println!("Cyclic groups of pages in the rules: {}", components.len());
+    for component in &components {
+        println!(
+            "  {} pages: {}",
+            component.pages.len(),
+            join(&component.pages, ", ")
+        );
+        println!("    e.g. {}", join(&component.cycle, " -> "));
+    }
+
+    let reports = check_updates(lookup, updates);
+
+    println!("Updates without a single correct order: {}", reports.len());
+    for report in &reports {
+        println!(
+            "  update {} ({}): {}",
+            report.index + 1,
+            join(&updates[report.index], ","),
+            report.error
+        );
+        if !report.rules.is_empty() {
+            let rules = report
+                .rules
+                .iter()
+                .map(|(left, right)| format!("{}|{}", left, right));
+            println!("    rules: {}", join(rules, ", "));
         }
-    });
+    }
 }
 
-fn get_sum(lookup: &BTreeMap<i32, Vec<i32>>, updates: &Vec<Vec<i32>>) -> (i32, Vec<Vec<i32>>) {
-    let mut invalid: Vec<Vec<i32>> = Vec::new();
+fn explain(lookup: &BTreeMap<i32, Vec<i32>>, updates: &[Vec<i32>]) {
+    let invalid = validate(lookup, updates);
 
-    let sum: i32 = updates
-        .iter()
-        .filter_map(|update| {
-            let mut previous: Vec<i32> = Vec::with_capacity(update.len());
-            let count = update
-                .iter()
-                .take_while(|n| {
-                    if let Some(matches) = lookup.get(n) {
-                        for p in &previous {
-                            if matches.contains(p) {
-                                return false;
-                            }
-                        }
-                    }
-                    previous.push(**n);
-                    true
-                })
-                .count();
-
-            if count == update.len() {
-                let middle = update.len() / 2;
-                update.get(middle)
+    println!("Invalid updates: {} of {}", invalid.len(), updates.len());
+    for update in &invalid {
+        println!(
+            "  update {} ({}):",
+            update.index + 1,
+            join(&updates[update.index], ",")
+        );
+        for violation in &update.violations {
+            let (left, right) = violation.rule;
+            let (later, earlier) = violation.positions;
+            println!(
+                "    {} ({} is at position {}, {} at position {})",
+                violation,
+                left,
+                later + 1,
+                right,
+                earlier + 1
+            );
+        }
+    }
+}
+
+fn repairs(lookup: &BTreeMap<i32, Vec<i32>>, updates: &[Vec<i32>]) {
+    let mut total = 0;
+    let mut sum = 0;
+
+    for update in validate(lookup, updates) {
+        let pages = &updates[update.index];
+        print!("  update {} ({}): ", update.index + 1, join(pages, ","));
+
+        match repair(lookup, pages) {
+            Ok(repaired) => {
+                let count = repaired.moves.len();
+                println!("{} move{}", count, if count == 1 { "" } else { "s" });
+                for m in &repaired.moves {
+                    println!(
+                        "    move {} from position {} to {}",
+                        m.page,
+                        m.from + 1,
+                        m.to + 1
+                    );
+                }
+                println!("    -> {}", join(&repaired.fixed, ","));
+
+                total += count;
+                sum += repaired.fixed[repaired.fixed.len() / 2];
+            }
+            Err(err) => println!("{}", err),
+        }
+    }
+
+    println!("Fewest moves in total: {}", total);
+    println!("Sum of repaired middle pages: {}", sum);
+}
+
+const QUERIES: &str = "before A B | unconstrained P | extendable A,B,...";
+
+// Answers one query, following chains of rules between the pages:
+//   before A B        must A be printed before B?
+//   unconstrained P   which pages may go either side of P?
+//   extendable A,B,C  can every page be put in an order that has these in this order?
+fn query(rules: &RuleSet, words: &[&str]) -> Result<String, String> {
+    let page = |word: &str| {
+        word.parse::<i32>()
+            .map_err(|_| format!("{} is not a page number", word))
+    };
+
+    match words {
+        ["before", a, b] => {
+            let (a, b) = (page(a)?, page(b)?);
+            let answer = if rules.must_precede(a, b) {
+                "yes"
             } else {
-                invalid.push(update.clone());
-                None
+                "no"
+            };
+            Ok(format!("{} must come before {}: {}", a, b, answer))
+        }
+        ["unconstrained", p] => {
+            let p = page(p)?;
+            let pages = rules.unconstrained(p);
+            let pages = if pages.is_empty() {
+                "none".to_string()
+            } else {
+                join(pages, ",")
+            };
+            Ok(format!("Pages that may go either side of {}: {}", p, pages))
+        }
+        ["extendable", sequence] => {
+            let sequence = sequence
+                .split(',')
+                .map(page)
+                .collect::<Result<Vec<_>, _>>()?;
+            let answer = if rules.is_extendable(&sequence) {
+                "yes"
+            } else {
+                "no"
+            };
+            Ok(format!(
+                "{} is extendable: {}",
+                join(&sequence, ","),
+                answer
+            ))
+        }
+        _ => Err(format!("Expected one of: {}", QUERIES)),
+    }
+}
+
+// `--query [--update N] [QUERY]`, where `--update` only uses the rules between the pages of
+// update N, since the full rules are often cyclic
+fn queries(lookup: &BTreeMap<i32, Vec<i32>>, updates: &[Vec<i32>], args: &[String]) {
+    let mut args = args.iter().map(|arg| arg.as_str()).collect::<Vec<_>>();
+
+    let rules = if args.first() == Some(&"--update") {
+        let update = args
+            .get(1)
+            .and_then(|number| number.parse::<usize>().ok())
+            .and_then(|number| number.checked_sub(1));
+        args.drain(..2.min(args.len()));
+
+        match update.and_then(|index| updates.get(index)) {
+            Some(update) => RuleSet::induced(lookup, update),
+            None => {
+                eprintln!("Expected an update number from 1 to {}", updates.len());
+                std::process::exit(1);
+            }
+        }
+    } else {
+        RuleSet::new(lookup)
+    };
+
+    if !args.is_empty() {
+        match query(&rules, &args) {
+            Ok(answer) => println!("{}", answer),
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
             }
+        }
+        return;
+    }
+
+    for line in std::io::stdin().lines().map_while(Result::ok) {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        if words.is_empty() {
+            continue;
+        }
+
+        match query(&rules, &words) {
+            Ok(answer) => println!("{}", answer),
+            Err(err) => println!("{}", err),
+        }
+    }
+}
+
+fn dot(lookup: &BTreeMap<i32, Vec<i32>>, updates: &[Vec<i32>], number: Option<&String>) {
+    let Some(number) = number else {
+        print!("{}", rules_to_dot(lookup));
+        return;
+    };
+
+    let update = number
+        .parse::<usize>()
+        .ok()
+        .and_then(|number| updates.get(number.checked_sub(1)?));
+
+    match update {
+        Some(update) => {
+            let highlight = Highlight {
+                violations: true,
+                order: true,
+            };
+            print!("{}", update_to_dot(lookup, update, highlight));
+        }
+        None => {
+            eprintln!(
+                "No update {}, there are {} numbered from 1",
+                number,
+                updates.len()
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+fn get_sum(lookup: &BTreeMap<i32, Vec<i32>>, updates: &[Vec<i32>]) -> (i32, Vec<Vec<i32>>) {
+    let invalid = validate(lookup, updates)
+        .into_iter()
+        .map(|update| update.index)
+        .collect::<BTreeSet<_>>();
+
+    let sum = updates
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !invalid.contains(index))
+        .filter_map(|(_, update)| {
+            let middle = update.len() / 2;
+            update.get(middle)
         })
-        .map(|n| *n)
         .sum();
 
+    let invalid = invalid
+        .into_iter()
+        .map(|index| updates[index].clone())
+        .collect();
+
     (sum, invalid)
 }
 
-fn sort_and_get_sum(lookup: &BTreeMap<i32, Vec<i32>>, updates: &mut Vec<Vec<i32>>) -> i32 {
-    updates.iter_mut().for_each(|update| {
-        update.sort_by(|a, b| {
-            if let Some(matches) = lookup.get(a) {
-                if matches.contains(b) {
-                    Less
-                } else {
-                    Greater
-                }
-            } else {
-                Equal
-            }
-        });
-    });
+// Sorts every update in place, or returns the first one that has no single correct order
+fn sort_and_get_sum(
+    lookup: &BTreeMap<i32, Vec<i32>>,
+    updates: &mut [Vec<i32>],
+) -> Result<i32, (Vec<i32>, OrderError)> {
+    for update in updates.iter_mut() {
+        *update = topological_order(lookup, update).map_err(|err| (update.clone(), err))?;
+    }
 
-    updates
+    Ok(updates
         .iter()
         .filter_map(|update| {
             let middle = update.len() / 2;
             update.get(middle)
         })
-        .sum::<i32>()
+        .sum::<i32>())
 }
@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Why an update's pages can't be put in a single correct order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderError {
+    /// The same page is listed more than once. `parse` already rejects such updates, so this
+    /// only comes from updates built by hand.
+    Repeated(i32),
+    /// No rule decides which of these two pages comes first. They are listed in the order
+    /// they appear in the update.
+    Ambiguous(i32, i32),
+    /// The rules between these pages form a cycle, so every order breaks one of them.
+    /// Pages are listed in the order they appear in the update.
+    Contradictory(Vec<i32>),
+}
+
+impl fmt::Display for OrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderError::Repeated(page) => write!(f, "page {} appears more than once", page),
+            OrderError::Ambiguous(a, b) => {
+                write!(f, "no rule decides whether {} or {} comes first", a, b)
+            }
+            OrderError::Contradictory(pages) => {
+                let pages = pages.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+                write!(f, "the rules between {} form a cycle", pages.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for OrderError {}
+
+/// Orders the pages of `update` so that every rule between them is followed, using Kahn's
+/// algorithm on the rules whose pages both appear in the update. `lookup` maps each page to
+/// the pages that must come after it.
+///
+/// The order must be the only one the rules allow, so that its middle page is well defined.
+pub fn topological_order(
+    lookup: &BTreeMap<i32, Vec<i32>>,
+    update: &[i32],
+) -> Result<Vec<i32>, OrderError> {
+    // Index in `update` of every page, so that the subgraph only holds these pages
+    let mut index = BTreeMap::new();
+    for (i, page) in update.iter().enumerate() {
+        if index.insert(*page, i).is_some() {
+            return Err(OrderError::Repeated(*page));
+        }
+    }
+
+    let mut after = vec![Vec::new(); update.len()];
+    let mut in_degree = vec![0; update.len()];
+
+    for (i, page) in update.iter().enumerate() {
+        for next in lookup.get(page).into_iter().flatten() {
+            if let Some(j) = index.get(next) {
+                if !after[i].contains(j) {
+                    after[i].push(*j);
+                    in_degree[*j] += 1;
+                }
+            }
+        }
+    }
+
+    let mut ready = (0..update.len())
+        .filter(|i| in_degree[*i] == 0)
+        .collect::<Vec<_>>();
+    let mut order = Vec::with_capacity(update.len());
+
+    while let Some(i) = ready.pop() {
+        if let Some(other) = ready.first() {
+            let (a, b) = (i.min(*other), i.max(*other));
+            return Err(OrderError::Ambiguous(update[a], update[b]));
+        }
+
+        order.push(update[i]);
+
+        for j in &after[i] {
+            in_degree[*j] -= 1;
+            if in_degree[*j] == 0 {
+                ready.push(*j);
+            }
+        }
+    }
+
+    if order.len() < update.len() {
+        // Pages left over wait on a cycle, but only drop out of it once nothing after them
+        // is left over either
+        let mut left = (0..update.len())
+            .map(|i| in_degree[i] > 0)
+            .collect::<Vec<_>>();
+
+        while let Some(i) =
+            (0..update.len()).find(|i| left[*i] && !after[*i].iter().any(|j| left[*j]))
+        {
+            left[i] = false;
+        }
+
+        let cycle = (0..update.len())
+            .filter(|i| left[*i])
+            .map(|i| update[i])
+            .collect();
+
+        return Err(OrderError::Contradictory(cycle));
+    }
+
+    Ok(order)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::{example, lookup};
+
+    #[test]
+    fn test_example() {
+        let (lookup, _) = example();
+
+        assert_eq!(
+            topological_order(&lookup, &[75, 97, 47, 61, 53]),
+            Ok(vec![97, 75, 47, 61, 53])
+        );
+        assert_eq!(
+            topological_order(&lookup, &[61, 13, 29]),
+            Ok(vec![61, 29, 13])
+        );
+        assert_eq!(
+            topological_order(&lookup, &[97, 13, 75, 29, 47]),
+            Ok(vec![97, 75, 47, 29, 13])
+        );
+
+        // Already in order
+        assert_eq!(
+            topological_order(&lookup, &[75, 47, 61, 53, 29]),
+            Ok(vec![75, 47, 61, 53, 29])
+        );
+    }
+
+    #[test]
+    fn test_rules_outside_the_update_are_ignored() {
+        // 1 before 2 only through 3, which isn't printed
+        let lookup = lookup("1|3\n3|2\n4|1\n4|2");
+
+        assert_eq!(
+            topological_order(&lookup, &[2, 1]),
+            Err(OrderError::Ambiguous(2, 1))
+        );
+        assert_eq!(topological_order(&lookup, &[2, 4]), Ok(vec![4, 2]));
+        assert_eq!(topological_order(&lookup, &[7]), Ok(vec![7]));
+        assert_eq!(topological_order(&lookup, &[]), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_ambiguous() {
+        let lookup = lookup("1|2\n1|3");
+        assert_eq!(
+            topological_order(&lookup, &[3, 2, 1]),
+            Err(OrderError::Ambiguous(3, 2))
+        );
+        assert_eq!(
+            topological_order(&lookup, &[3, 2, 1])
+                .unwrap_err()
+                .to_string(),
+            "no rule decides whether 3 or 2 comes first"
+        );
+    }
+
+    #[test]
+    fn test_contradictory() {
+        let lookup = lookup("1|2\n2|3\n3|1\n0|1\n3|4");
+
+        assert_eq!(
+            topological_order(&lookup, &[4, 3, 2, 1, 0]),
+            Err(OrderError::Contradictory(vec![3, 2, 1]))
+        );
+        assert_eq!(
+            topological_order(&lookup, &[1, 2, 3]),
+            Err(OrderError::Contradictory(vec![1, 2, 3]))
+        );
+        assert_eq!(topological_order(&lookup, &[2, 1]), Ok(vec![1, 2]));
+    }
+
+    #[test]
+    fn test_repeated() {
+        let (lookup, _) = example();
+        assert_eq!(
+            topological_order(&lookup, &[75, 47, 75]),
+            Err(OrderError::Repeated(75))
+        );
+    }
+}
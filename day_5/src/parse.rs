@@ -0,0 +1,361 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Why the input couldn't be read. Lines and columns are numbered from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// Something other than what `expected` describes at this position.
+    Malformed {
+        line: usize,
+        column: usize,
+        expected: &'static str,
+    },
+    /// A rule that was already given on line `first`.
+    DuplicateRule {
+        line: usize,
+        first: usize,
+        rule: (i32, i32),
+    },
+    /// A page listed twice in one update.
+    RepeatedPage {
+        line: usize,
+        column: usize,
+        page: i32,
+    },
+    /// An update with an even number of pages, which has no middle page.
+    NoMiddlePage { line: usize, pages: usize },
+    /// The rules aren't followed by a blank line and the updates.
+    MissingUpdates,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Malformed {
+                line,
+                column,
+                expected,
+            } => write!(f, "line {}, column {}: expected {}", line, column, expected),
+            ParseError::DuplicateRule {
+                line,
+                first,
+                rule: (left, right),
+            } => write!(
+                f,
+                "line {}: rule {}|{} was already given on line {}",
+                line, left, right, first
+            ),
+            ParseError::RepeatedPage { line, column, page } => write!(
+                f,
+                "line {}, column {}: page {} is already in this update",
+                line, column, page
+            ),
+            ParseError::NoMiddlePage { line, pages } => write!(
+                f,
+                "line {}: an update needs an odd number of pages to have a middle one, found {}",
+                line, pages
+            ),
+            ParseError::MissingUpdates => {
+                write!(f, "expected a blank line after the rules, then the updates")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Something suspicious in input that could still be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// A page that no rule mentions, so it may go anywhere in its update. `line` is where it
+    /// first appears.
+    Unconstrained { line: usize, page: i32 },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::Unconstrained { line, page } => {
+                write!(f, "line {}: no rule mentions page {}", line, page)
+            }
+        }
+    }
+}
+
+/// The rules, as a map from each page to the pages that must come after it, and the updates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Input {
+    pub lookup: BTreeMap<i32, Vec<i32>>,
+    pub updates: Vec<Vec<i32>>,
+    pub warnings: Vec<Warning>,
+}
+
+const PAGE: &str = "a page number";
+
+/// Reads one `X|Y` rule per line, a blank line, then one comma separated update per line.
+/// Blank lines after the last update are ignored.
+pub fn parse(input: &str) -> Result<Input, ParseError> {
+    let mut lines = input.lines().enumerate().map(|(i, line)| (i + 1, line));
+
+    let mut lookup: BTreeMap<i32, Vec<i32>> = BTreeMap::new();
+    let mut seen = BTreeMap::new();
+    let mut separated = false;
+
+    for (line, text) in lines.by_ref() {
+        if text.is_empty() {
+            separated = true;
+            break;
+        }
+
+        let (left, right) = parse_rule(line, text)?;
+
+        if let Some(first) = seen.insert((left, right), line) {
+            return Err(ParseError::DuplicateRule {
+                line,
+                first,
+                rule: (left, right),
+            });
+        }
+
+        lookup.entry(left).or_default().push(right);
+    }
+
+    if !separated {
+        return Err(ParseError::MissingUpdates);
+    }
+
+    let mentioned = lookup
+        .iter()
+        .flat_map(|(left, rights)| std::iter::once(left).chain(rights))
+        .copied()
+        .collect::<BTreeSet<_>>();
+
+    let mut updates = Vec::new();
+    let mut warnings = Vec::new();
+    let mut warned = BTreeSet::new();
+
+    let mut lines = lines.collect::<Vec<_>>();
+    while lines.last().is_some_and(|(_, text)| text.is_empty()) {
+        lines.pop();
+    }
+
+    for (line, text) in lines {
+        let update = parse_update(line, text)?;
+
+        for page in &update {
+            if !mentioned.contains(page) && warned.insert(*page) {
+                warnings.push(Warning::Unconstrained { line, page: *page });
+            }
+        }
+
+        updates.push(update);
+    }
+
+    Ok(Input {
+        lookup,
+        updates,
+        warnings,
+    })
+}
+
+// The number in `text`, which starts at `column`
+fn parse_page(line: usize, column: usize, text: &str) -> Result<i32, ParseError> {
+    let malformed = ParseError::Malformed {
+        line,
+        column,
+        expected: PAGE,
+    };
+
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(malformed);
+    }
+
+    text.parse().map_err(|_| malformed)
+}
+
+fn parse_rule(line: usize, text: &str) -> Result<(i32, i32), ParseError> {
+    let Some((left, right)) = text.split_once('|') else {
+        return Err(ParseError::Malformed {
+            line,
+            column: text.chars().count() + 1,
+            expected: "a rule like 47|53",
+        });
+    };
+
+    let left_page = parse_page(line, 1, left)?;
+
+    let column = left.chars().count() + 2;
+    if let Some(extra) = right.find('|') {
+        return Err(ParseError::Malformed {
+            line,
+            column: column + right[..extra].chars().count(),
+            expected: "the end of the rule",
+        });
+    }
+
+    Ok((left_page, parse_page(line, column, right)?))
+}
+
+fn parse_update(line: usize, text: &str) -> Result<Vec<i32>, ParseError> {
+    let mut update = Vec::new();
+    let mut column = 1;
+
+    for token in text.split(',') {
+        let page = parse_page(line, column, token)?;
+
+        if update.contains(&page) {
+            return Err(ParseError::RepeatedPage { line, column, page });
+        }
+
+        update.push(page);
+        column += token.chars().count() + 1;
+    }
+
+    if update.len() % 2 == 0 {
+        return Err(ParseError::NoMiddlePage {
+            line,
+            pages: update.len(),
+        });
+    }
+
+    Ok(update)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::EXAMPLE;
+
+    #[test]
+    fn test_example() {
+        let input = parse(EXAMPLE).unwrap();
+
+        assert_eq!(input.lookup[&47], [53, 13, 61, 29]);
+        assert_eq!(input.lookup.values().map(Vec::len).sum::<usize>(), 21);
+        assert_eq!(input.updates.len(), 6);
+        assert_eq!(input.updates[5], [97, 13, 75, 29, 47]);
+        assert!(input.warnings.is_empty());
+    }
+
+    fn error(input: &str) -> String {
+        parse(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_malformed_rules() {
+        assert_eq!(
+            error("1|2|3\n\n1,2"),
+            "line 1, column 4: expected the end of the rule"
+        );
+        assert_eq!(
+            error("1|2\n12\n\n1,2"),
+            "line 2, column 3: expected a rule like 47|53"
+        );
+        assert_eq!(
+            error("1|x\n\n1,2"),
+            "line 1, column 3: expected a page number"
+        );
+        assert_eq!(
+            error("|2\n\n1,2"),
+            "line 1, column 1: expected a page number"
+        );
+        assert_eq!(
+            error("1|-2\n\n1,2"),
+            "line 1, column 3: expected a page number"
+        );
+        assert_eq!(
+            error("1| 2\n\n1,2"),
+            "line 1, column 3: expected a page number"
+        );
+
+        // Updates without the blank line first
+        assert_eq!(
+            error("1|2\n1,2"),
+            "line 2, column 4: expected a rule like 47|53"
+        );
+        assert_eq!(parse("1|2\n"), Err(ParseError::MissingUpdates));
+    }
+
+    #[test]
+    fn test_malformed_updates() {
+        assert_eq!(
+            error("1|2\n\n1,2,3\n1,x,2"),
+            "line 4, column 3: expected a page number"
+        );
+        assert_eq!(
+            error("1|2\n\n1,2,\n"),
+            "line 3, column 5: expected a page number"
+        );
+        assert_eq!(
+            error("1|2\n\n1,2,3\n\n2,1,3"),
+            "line 4, column 1: expected a page number"
+        );
+        assert_eq!(
+            error("1|2\n\n1;2"),
+            "line 3, column 1: expected a page number"
+        );
+        assert_eq!(
+            error("1|2\n\n1,99999999999"),
+            "line 3, column 3: expected a page number"
+        );
+    }
+
+    #[test]
+    fn test_duplicates() {
+        assert_eq!(
+            parse("1|2\n3|4\n1|2\n\n1,2"),
+            Err(ParseError::DuplicateRule {
+                line: 3,
+                first: 1,
+                rule: (1, 2),
+            })
+        );
+        assert_eq!(
+            error("1|2\n\n1,2,3,2"),
+            "line 3, column 7: page 2 is already in this update"
+        );
+
+        // The same rule both ways is a contradiction, not a duplicate
+        assert!(parse("1|2\n2|1\n1|3\n\n1,2,3").is_ok());
+    }
+
+    #[test]
+    fn test_warnings() {
+        let input = parse("1|2\n\n1,2,7\n7,1,8\n").unwrap();
+
+        assert_eq!(
+            input.warnings,
+            [
+                Warning::Unconstrained { line: 3, page: 7 },
+                Warning::Unconstrained { line: 4, page: 8 },
+            ]
+        );
+        assert_eq!(
+            input.warnings[0].to_string(),
+            "line 3: no rule mentions page 7"
+        );
+
+        // No updates at all is fine
+        assert!(parse("1|2\n\n").unwrap().updates.is_empty());
+    }
+
+    #[test]
+    fn test_trailing_blank_lines() {
+        let input = parse(&format!("{}\n\n", EXAMPLE)).unwrap();
+        assert_eq!(input.updates.len(), 6);
+
+        assert_eq!(parse("1|2\n\n1\n\n\n").unwrap().updates, [[1]]);
+    }
+
+    #[test]
+    fn test_updates_without_a_middle() {
+        assert_eq!(
+            parse("1|2\n\n1,2,3\n2,1\n"),
+            Err(ParseError::NoMiddlePage { line: 4, pages: 2 })
+        );
+        assert_eq!(
+            error("1|2\n\n1,2,3,4"),
+            "line 3: an update needs an odd number of pages to have a middle one, found 4"
+        );
+    }
+}
@@ -0,0 +1,382 @@
+use crate::order::OrderError;
+use crate::rules::RuleSet;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Takes `page` out of the update at index `from` and puts it back so that it ends up at
+/// index `to`. Indices are from 0 and refer to the update as it is just before the move.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Move {
+    pub page: i32,
+    pub from: usize,
+    pub to: usize,
+}
+
+/// The fewest moves that put an update in order, and the order they lead to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repair {
+    pub moves: Vec<Move>,
+    pub fixed: Vec<i32>,
+}
+
+/// Finds the fewest single page moves that make `update` follow the rules.
+///
+/// Pages that aren't moved keep their order, so no rule, followed through any chain of pages
+/// in the update, may put one of them before another listed ahead of it. The most pages that
+/// fit that stay put and every other page is moved once, to a place its rules allow. When the
+/// rules leave some pages free to go either way, they are kept as close to the order they
+/// were listed in as possible. An update with a cycle in its rules can't be repaired, and
+/// neither can one built by hand that lists a page twice, which `parse` never returns.
+pub fn repair(lookup: &BTreeMap<i32, Vec<i32>>, update: &[i32]) -> Result<Repair, OrderError> {
+    for (i, page) in update.iter().enumerate() {
+        if update[..i].contains(page) {
+            return Err(OrderError::Repeated(*page));
+        }
+    }
+
+    let rules = RuleSet::induced(lookup, update);
+    if !rules.is_acyclic() {
+        let cycle = update
+            .iter()
+            .copied()
+            .filter(|page| rules.must_precede(*page, *page))
+            .collect();
+        return Err(OrderError::Contradictory(cycle));
+    }
+
+    let stay = stay(&rules, update);
+    let fixed = complete(lookup, update, &stay);
+
+    let rank = fixed
+        .iter()
+        .enumerate()
+        .map(|(i, page)| (*page, i))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut placed = vec![false; fixed.len()];
+    for (page, stays) in update.iter().zip(&stay) {
+        placed[rank[page]] = *stays;
+    }
+
+    let mut current = update.to_vec();
+    let mut moves = Vec::new();
+
+    for (r, page) in fixed.iter().enumerate() {
+        if placed[r] {
+            continue;
+        }
+
+        let from = current.iter().position(|p| p == page).unwrap();
+        current.remove(from);
+
+        // Straight after the closest page before it that is already where it belongs
+        let to = (0..r)
+            .rev()
+            .find(|before| placed[*before])
+            .map_or(0, |before| {
+                current.iter().position(|p| *p == fixed[before]).unwrap() + 1
+            });
+        current.insert(to, *page);
+
+        placed[r] = true;
+        moves.push(Move {
+            page: *page,
+            from,
+            to,
+        });
+    }
+
+    debug_assert_eq!(current, fixed);
+
+    Ok(Repair { moves, fixed })
+}
+
+// Marks the positions of the most pages that can stay where they are.
+//
+// Two pages clash when the later one must come before the earlier one. Clashes are transitive,
+// so they order the positions, and the pages that stay are a largest set of positions that
+// don't clash with each other. That is found from a maximum matching between earlier and later
+// positions that clash, by Dilworth's and König's theorems.
+fn stay(rules: &RuleSet, update: &[i32]) -> Vec<bool> {
+    let len = update.len();
+
+    let clashes = (0..len)
+        .map(|i| {
+            (i + 1..len)
+                .filter(|j| rules.must_precede(update[*j], update[i]))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    // The earlier position matched to each later one
+    let mut matched = vec![None; len];
+    for i in 0..len {
+        augment(i, &clashes, &mut matched, &mut vec![false; len]);
+    }
+
+    // Follow alternating paths from the earlier positions left unmatched
+    let mut earlier = vec![true; len];
+    for i in matched.iter().flatten() {
+        earlier[*i] = false;
+    }
+    let mut later = vec![false; len];
+    let mut stack = (0..len).filter(|i| earlier[*i]).collect::<Vec<_>>();
+
+    while let Some(i) = stack.pop() {
+        for j in &clashes[i] {
+            if later[*j] {
+                continue;
+            }
+            later[*j] = true;
+
+            if let Some(k) = matched[*j] {
+                if !earlier[k] {
+                    earlier[k] = true;
+                    stack.push(k);
+                }
+            }
+        }
+    }
+
+    (0..len).map(|i| earlier[i] && !later[i]).collect()
+}
+
+// Kuhn's augmenting path step, matching `i` to a later position if it can
+fn augment(
+    i: usize,
+    clashes: &[Vec<usize>],
+    matched: &mut [Option<usize>],
+    seen: &mut [bool],
+) -> bool {
+    for j in &clashes[i] {
+        if seen[*j] {
+            continue;
+        }
+        seen[*j] = true;
+
+        if matched[*j].is_none_or(|k| augment(k, clashes, matched, seen)) {
+            matched[*j] = Some(i);
+            return true;
+        }
+    }
+
+    false
+}
+
+// An order of the pages that follows every rule and keeps the pages that `stay` in the order
+// they're listed in. Of the pages that could go next, the one listed first does.
+fn complete(lookup: &BTreeMap<i32, Vec<i32>>, update: &[i32], stay: &[bool]) -> Vec<i32> {
+    let index = update
+        .iter()
+        .enumerate()
+        .map(|(i, page)| (*page, i))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut after = vec![Vec::new(); update.len()];
+    for (i, page) in update.iter().enumerate() {
+        for next in lookup.get(page).into_iter().flatten() {
+            if let Some(j) = index.get(next) {
+                after[i].push(*j);
+            }
+        }
+    }
+
+    let staying = (0..update.len()).filter(|i| stay[*i]).collect::<Vec<_>>();
+    for pair in staying.windows(2) {
+        after[pair[0]].push(pair[1]);
+    }
+
+    let mut in_degree = vec![0; update.len()];
+    for j in after.iter().flatten() {
+        in_degree[*j] += 1;
+    }
+
+    let mut ready = (0..update.len())
+        .filter(|i| in_degree[*i] == 0)
+        .collect::<BTreeSet<_>>();
+    let mut order = Vec::with_capacity(update.len());
+
+    while let Some(i) = ready.pop_first() {
+        order.push(update[i]);
+
+        for j in &after[i] {
+            in_degree[*j] -= 1;
+            if in_degree[*j] == 0 {
+                ready.insert(*j);
+            }
+        }
+    }
+
+    order
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::{example, lookup};
+    use crate::validate::violations;
+    use std::collections::VecDeque;
+
+    fn apply(update: &[i32], moves: &[Move]) -> Vec<i32> {
+        let mut update = update.to_vec();
+        for m in moves {
+            assert_eq!(update[m.from], m.page);
+            update.remove(m.from);
+            update.insert(m.to, m.page);
+        }
+        update
+    }
+
+    // Fewest moves that leave `start` breaking no rule, trying every move
+    fn fewest_moves(lookup: &BTreeMap<i32, Vec<i32>>, start: &[i32]) -> usize {
+        let mut seen = BTreeSet::from([start.to_vec()]);
+        let mut queue = VecDeque::from([(start.to_vec(), 0)]);
+
+        while let Some((update, moves)) = queue.pop_front() {
+            if violations(lookup, &update).is_empty() {
+                return moves;
+            }
+            for from in 0..update.len() {
+                for to in 0..update.len() {
+                    let mut next = update.clone();
+                    let page = next.remove(from);
+                    next.insert(to, page);
+                    if seen.insert(next.clone()) {
+                        queue.push_back((next, moves + 1));
+                    }
+                }
+            }
+        }
+
+        unreachable!()
+    }
+
+    // Every order of `pages`
+    fn permutations(pages: &[i32]) -> Vec<Vec<i32>> {
+        if pages.is_empty() {
+            return vec![Vec::new()];
+        }
+
+        (0..pages.len())
+            .flat_map(|i| {
+                let mut rest = pages.to_vec();
+                let first = rest.remove(i);
+                permutations(&rest).into_iter().map(move |mut order| {
+                    order.insert(0, first);
+                    order
+                })
+            })
+            .collect()
+    }
+
+    fn check(lookup: &BTreeMap<i32, Vec<i32>>, update: &[i32]) -> Repair {
+        let repaired = repair(lookup, update).unwrap();
+
+        assert_eq!(apply(update, &repaired.moves), repaired.fixed);
+        assert!(violations(lookup, &repaired.fixed).is_empty());
+        assert_eq!(repaired.moves.len(), fewest_moves(lookup, update));
+
+        repaired
+    }
+
+    #[test]
+    fn test_example() {
+        let (lookup, updates) = example();
+
+        let moves = updates
+            .iter()
+            .map(|update| check(&lookup, update).moves.len())
+            .collect::<Vec<_>>();
+        assert_eq!(moves, [0, 0, 0, 1, 1, 2]);
+
+        // The same middle pages as sorting in part 2
+        let sum = updates[3..]
+            .iter()
+            .map(|update| {
+                let fixed = repair(&lookup, update).unwrap().fixed;
+                fixed[fixed.len() / 2]
+            })
+            .sum::<i32>();
+        assert_eq!(sum, 123);
+    }
+
+    #[test]
+    fn test_unconstrained_page() {
+        let lookup = lookup("1|2");
+
+        let repaired = check(&lookup, &[2, 1, 3]);
+        assert_eq!(repaired.fixed, [1, 2, 3]);
+        assert_eq!(repaired.moves.len(), 1);
+
+        // A page no rule mentions never has to move
+        let repaired = check(&lookup, &[3, 2, 1]);
+        assert_eq!(repaired.moves.len(), 1);
+        assert_eq!(repaired.fixed[0], 3);
+
+        assert_eq!(check(&lookup, &[7, 1, 2]).moves, []);
+    }
+
+    #[test]
+    fn test_partial_order() {
+        // 2 and 3 both come after 1, with nothing between them; 4 after 3, and 5 before 4
+        let lookup = lookup("1|2\n1|3\n3|4\n5|4");
+
+        // 3, 2 and 5 can stay where they are
+        let repaired = check(&lookup, &[4, 3, 2, 1, 5]);
+        assert_eq!(repaired.moves.len(), 2);
+        assert_eq!(repaired.fixed, [1, 3, 2, 5, 4]);
+
+        // Pages free to go either way stay in the order they were listed
+        let repaired = check(&lookup, &[3, 2, 1]);
+        assert_eq!(
+            repaired.moves,
+            [Move {
+                page: 1,
+                from: 2,
+                to: 0,
+            }]
+        );
+        assert_eq!(repaired.fixed, [1, 3, 2]);
+    }
+
+    #[test]
+    fn test_unrepairable() {
+        assert_eq!(
+            repair(&lookup("1|2\n2|3\n3|1\n3|4"), &[4, 2, 1, 3]),
+            Err(OrderError::Contradictory(vec![2, 1, 3]))
+        );
+        // Without the page that closes the cycle, the rest can be put in order
+        assert_eq!(
+            repair(&lookup("1|2\n2|3\n3|1"), &[2, 1]).unwrap().fixed,
+            [1, 2]
+        );
+    }
+
+    #[test]
+    fn test_repeated_page() {
+        assert_eq!(
+            repair(&lookup("1|2"), &[1, 2, 1]),
+            Err(OrderError::Repeated(1))
+        );
+        assert_eq!(repair(&lookup(""), &[3, 3]), Err(OrderError::Repeated(3)));
+    }
+
+    #[test]
+    fn test_moves_are_fewest() {
+        // Every arrangement of five pages, under rules that order all of them, some of them,
+        // chain through pages that are missing, or leave one page out
+        let rules = [
+            "10|20\n10|30\n10|40\n10|50\n20|30\n20|40\n20|50\n30|40\n30|50\n40|50",
+            "10|20\n10|30\n30|40",
+            "10|20\n20|30\n40|50\n50|30",
+            "10|99\n99|20\n20|30\n40|30",
+            "",
+        ];
+
+        for rules in rules {
+            let lookup = lookup(rules);
+            for update in permutations(&[10, 20, 30, 40, 50]) {
+                check(&lookup, &update);
+            }
+        }
+    }
+}
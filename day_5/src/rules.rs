@@ -0,0 +1,232 @@
+use std::collections::BTreeMap;
+
+// One bit per page, packed into words
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bits(Vec<u64>);
+
+impl Bits {
+    fn new(len: usize) -> Self {
+        Self(vec![0; len.div_ceil(64)])
+    }
+
+    fn get(&self, i: usize) -> bool {
+        self.0[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    fn set(&mut self, i: usize) {
+        self.0[i / 64] |= 1 << (i % 64);
+    }
+
+    fn union(&mut self, other: &Bits) {
+        for (word, other) in self.0.iter_mut().zip(&other.0) {
+            *word |= other;
+        }
+    }
+}
+
+/// The ordering rules, with every precedence they imply through chains of rules worked out
+/// up front.
+#[derive(Debug, Clone)]
+pub struct RuleSet {
+    // Every page named by a rule, sorted, and the index of each in `pages`
+    pages: Vec<i32>,
+    index: BTreeMap<i32, usize>,
+    // Bit `j` of `before[i]` is set when page `i` must come before page `j`
+    before: Vec<Bits>,
+}
+
+impl RuleSet {
+    /// Builds the rule set from a map of each page to the pages that must come after it.
+    pub fn new(lookup: &BTreeMap<i32, Vec<i32>>) -> Self {
+        let mut pages = lookup
+            .iter()
+            .flat_map(|(left, rights)| std::iter::once(left).chain(rights))
+            .copied()
+            .collect::<Vec<_>>();
+        pages.sort_unstable();
+        pages.dedup();
+
+        Self::with_pages(lookup, pages)
+    }
+
+    /// Builds the rule set from only the rules between `pages`, like the pages of one update.
+    /// On the full rules, pages often all precede each other through some cycle, while the
+    /// rules between a few of them still put them in order.
+    pub fn induced(lookup: &BTreeMap<i32, Vec<i32>>, pages: &[i32]) -> Self {
+        let mut pages = pages.to_vec();
+        pages.sort_unstable();
+        pages.dedup();
+
+        Self::with_pages(lookup, pages)
+    }
+
+    fn with_pages(lookup: &BTreeMap<i32, Vec<i32>>, pages: Vec<i32>) -> Self {
+        let index = pages
+            .iter()
+            .enumerate()
+            .map(|(i, page)| (*page, i))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut before = vec![Bits::new(pages.len()); pages.len()];
+        for (left, rights) in lookup {
+            let Some(i) = index.get(left) else {
+                continue;
+            };
+            for right in rights {
+                if let Some(j) = index.get(right) {
+                    before[*i].set(*j);
+                }
+            }
+        }
+
+        // Warshall's algorithm a row at a time: once every page that reaches `k` also reaches
+        // what `k` does, paths through `k` are accounted for
+        for k in 0..pages.len() {
+            let through = before[k].clone();
+            for row in before.iter_mut() {
+                if row.get(k) {
+                    row.union(&through);
+                }
+            }
+        }
+
+        Self {
+            pages,
+            index,
+            before,
+        }
+    }
+
+    pub fn pages(&self) -> &[i32] {
+        &self.pages
+    }
+
+    /// Whether the rules, followed through any chain of pages, put `a` before `b`.
+    pub fn must_precede(&self, a: i32, b: i32) -> bool {
+        match (self.index.get(&a), self.index.get(&b)) {
+            (Some(i), Some(j)) => self.before[*i].get(*j),
+            _ => false,
+        }
+    }
+
+    /// Whether no chain of rules leads from a page back to itself.
+    pub fn is_acyclic(&self) -> bool {
+        (0..self.pages.len()).all(|i| !self.before[i].get(i))
+    }
+
+    /// Pages that may go either before or after `page`, because no chain of rules links them.
+    pub fn unconstrained(&self, page: i32) -> Vec<i32> {
+        self.pages
+            .iter()
+            .copied()
+            .filter(|other| {
+                *other != page
+                    && !self.must_precede(page, *other)
+                    && !self.must_precede(*other, page)
+            })
+            .collect()
+    }
+
+    /// Whether some order of all the pages follows every rule and has `sequence` in it, in
+    /// that order if not next to each other. There is no such order if the rules have a cycle.
+    pub fn is_extendable(&self, sequence: &[i32]) -> bool {
+        if !self.is_acyclic() {
+            return false;
+        }
+
+        sequence.iter().enumerate().all(|(i, a)| {
+            sequence[i + 1..]
+                .iter()
+                .all(|b| a != b && !self.must_precede(*b, *a))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::{example, lookup};
+
+    fn rules(rules: &str) -> RuleSet {
+        RuleSet::new(&lookup(rules))
+    }
+
+    #[test]
+    fn test_transitive() {
+        let rules = rules("1|2\n2|3\n3|4\n5|4");
+
+        assert!(rules.must_precede(1, 2));
+        assert!(rules.must_precede(1, 4));
+        assert!(!rules.must_precede(4, 1));
+        assert!(!rules.must_precede(1, 5));
+        assert!(!rules.must_precede(1, 1));
+        assert!(!rules.must_precede(1, 99));
+        assert!(rules.is_acyclic());
+    }
+
+    #[test]
+    fn test_unconstrained() {
+        let rules = rules("1|2\n2|3\n3|4\n5|4");
+
+        assert_eq!(rules.unconstrained(5), [1, 2, 3]);
+        assert_eq!(rules.unconstrained(1), [5]);
+        assert!(rules.unconstrained(4).is_empty());
+        assert_eq!(rules.unconstrained(99), [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_extendable() {
+        let (lookup, _) = example();
+        let example = RuleSet::new(&lookup);
+
+        assert!(example.is_acyclic());
+        assert!(example.is_extendable(&[97, 75, 47, 61, 53]));
+        assert!(example.is_extendable(&[97, 13]));
+        assert!(example.is_extendable(&[]));
+        assert!(!example.is_extendable(&[75, 97]));
+        assert!(!example.is_extendable(&[13, 75]));
+        assert!(!example.is_extendable(&[47, 47]));
+
+        // 1 comes before 4 only through other pages
+        let chain = rules("1|2\n2|3\n3|4\n5|4");
+        assert!(!chain.is_extendable(&[4, 1]));
+        assert!(chain.is_extendable(&[5, 1, 4]));
+        assert!(chain.is_extendable(&[1, 5, 3]));
+    }
+
+    #[test]
+    fn test_cycles() {
+        let lookup = lookup("1|2\n2|3\n3|1");
+        let rules = RuleSet::new(&lookup);
+
+        assert!(!rules.is_acyclic());
+        assert!(rules.must_precede(1, 3) && rules.must_precede(3, 1));
+        assert!(rules.must_precede(2, 2));
+        assert!(!rules.is_extendable(&[1, 2]));
+
+        // Without the third page, two of them are in order again
+        let update = RuleSet::induced(&lookup, &[3, 1]);
+        assert!(update.is_acyclic());
+        assert!(update.must_precede(3, 1));
+        assert!(!update.must_precede(1, 3));
+        assert!(update.is_extendable(&[3, 1]));
+        assert_eq!(update.pages(), [1, 3]);
+    }
+
+    #[test]
+    fn test_many_pages() {
+        // A chain long enough to span several words of bits
+        let lookup = (0..200)
+            .map(|i| (i, vec![i + 1]))
+            .collect::<BTreeMap<_, _>>();
+        let rules = RuleSet::new(&lookup);
+
+        assert!(rules.must_precede(0, 200));
+        assert!(rules.must_precede(63, 64));
+        assert!(rules.must_precede(5, 130));
+        assert!(!rules.must_precede(130, 5));
+        assert!(rules.unconstrained(100).is_empty());
+        assert!(rules.is_extendable(&[3, 70, 199]));
+        assert!(!rules.is_extendable(&[3, 199, 70]));
+    }
+}
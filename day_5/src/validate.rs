@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A rule `X|Y` that an update breaks by printing `Y` before `X`. Positions are indices into
+/// the update, from 0.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub rule: (i32, i32),
+    pub positions: (usize, usize),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (left, right) = self.rule;
+        write!(
+            f,
+            "{} appears after {} but rule {}|{} requires it first",
+            left, right, left, right
+        )
+    }
+}
+
+/// An update that breaks at least one rule. `index` is its position in the input, from 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidUpdate {
+    pub index: usize,
+    pub violations: Vec<Violation>,
+}
+
+/// Returns every rule that `update` breaks, in order of where the later page appears and
+/// then the earlier one. A page listed more than once is checked at every position, though
+/// only updates built by hand can have one, since `parse` rejects them.
+pub fn violations(lookup: &BTreeMap<i32, Vec<i32>>, update: &[i32]) -> Vec<Violation> {
+    let mut positions: BTreeMap<i32, Vec<usize>> = BTreeMap::new();
+    for (i, page) in update.iter().enumerate() {
+        positions.entry(*page).or_default().push(i);
+    }
+
+    let mut violations = Vec::new();
+
+    for (i, left) in update.iter().enumerate() {
+        for right in lookup.get(left).into_iter().flatten() {
+            for j in positions.get(right).into_iter().flatten() {
+                if *j < i {
+                    violations.push(Violation {
+                        rule: (*left, *right),
+                        positions: (i, *j),
+                    });
+                }
+            }
+        }
+    }
+
+    violations.sort_by_key(|violation| violation.positions);
+    violations.dedup();
+    violations
+}
+
+/// Returns every update that breaks a rule, with all the rules it breaks.
+pub fn validate(lookup: &BTreeMap<i32, Vec<i32>>, updates: &[Vec<i32>]) -> Vec<InvalidUpdate> {
+    updates
+        .iter()
+        .enumerate()
+        .filter_map(|(index, update)| {
+            let violations = violations(lookup, update);
+            (!violations.is_empty()).then_some(InvalidUpdate { index, violations })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fixtures::{example, lookup};
+
+    #[test]
+    fn test_example() {
+        let (lookup, updates) = example();
+        let invalid = validate(&lookup, &updates);
+
+        assert_eq!(
+            invalid.iter().map(|u| u.index).collect::<Vec<_>>(),
+            [3, 4, 5]
+        );
+
+        assert_eq!(
+            invalid[0].violations,
+            [Violation {
+                rule: (97, 75),
+                positions: (1, 0),
+            }]
+        );
+        assert_eq!(
+            invalid[1].violations,
+            [Violation {
+                rule: (29, 13),
+                positions: (2, 1),
+            }]
+        );
+
+        let rules = invalid[2]
+            .violations
+            .iter()
+            .map(|v| v.rule)
+            .collect::<Vec<_>>();
+        assert_eq!(rules, [(75, 13), (29, 13), (47, 13), (47, 29)]);
+    }
+
+    #[test]
+    fn test_explanation() {
+        let violations = violations(&lookup("75|97"), &[97, 75]);
+
+        assert_eq!(
+            violations[0].to_string(),
+            "75 appears after 97 but rule 75|97 requires it first"
+        );
+        assert_eq!(violations[0].positions, (1, 0));
+    }
+
+    #[test]
+    fn test_repeated_pages() {
+        let violations = violations(&lookup("1|2"), &[2, 1, 2]);
+
+        assert_eq!(
+            violations,
+            [Violation {
+                rule: (1, 2),
+                positions: (1, 0),
+            }]
+        );
+        assert!(validate(&lookup("1|2"), &[vec![1, 2], vec![3]]).is_empty());
+    }
+}
@@ -0,0 +1,278 @@
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+/// A rectangular grid stored row-major in a single `Vec`. Positions are `(row, col)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    rows: usize,
+    cols: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Panics if `cells` doesn't hold exactly `rows * cols` values.
+    pub fn new(rows: usize, cols: usize, cells: Vec<T>) -> Self {
+        assert_eq!(
+            cells.len(),
+            rows * cols,
+            "a {}x{} grid needs {} cells",
+            rows,
+            cols,
+            rows * cols
+        );
+
+        Self { rows, cols, cells }
+    }
+
+    pub fn filled(rows: usize, cols: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self::new(rows, cols, vec![value; rows * cols])
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn contains(&self, (row, col): (usize, usize)) -> bool {
+        row < self.rows && col < self.cols
+    }
+
+    pub fn get(&self, pos: (usize, usize)) -> Option<&T> {
+        self.contains(pos)
+            .then(|| &self.cells[pos.0 * self.cols + pos.1])
+    }
+
+    pub fn get_mut(&mut self, pos: (usize, usize)) -> Option<&mut T> {
+        if self.contains(pos) {
+            Some(&mut self.cells[pos.0 * self.cols + pos.1])
+        } else {
+            None
+        }
+    }
+
+    /// The position one `(dr, dc)` step away from `pos`, if it's inside the grid.
+    pub fn offset(
+        &self,
+        (row, col): (usize, usize),
+        (dr, dc): (isize, isize),
+    ) -> Option<(usize, usize)> {
+        let pos = (row.checked_add_signed(dr)?, col.checked_add_signed(dc)?);
+        self.contains(pos).then_some(pos)
+    }
+
+    /// The value one `(dr, dc)` step away from `pos`, if it's inside the grid.
+    pub fn neighbor(&self, pos: (usize, usize), delta: (isize, isize)) -> Option<&T> {
+        self.offset(pos, delta).map(|pos| &self[pos])
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.cells[row * self.cols..(row + 1) * self.cols]
+    }
+
+    pub fn iter_rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.cols.max(1)).take(self.rows)
+    }
+
+    pub fn column(&self, col: usize) -> impl Iterator<Item = &T> {
+        assert!(col < self.cols, "column {} is out of bounds", col);
+        self.cells[col..].iter().step_by(self.cols)
+    }
+
+    /// Cells from `start` onwards in steps of `step`, until the edge of the grid.
+    pub fn ray(&self, start: (usize, usize), step: (isize, isize)) -> Ray<'_, T> {
+        Ray {
+            grid: self,
+            pos: self.contains(start).then_some(start),
+            step,
+        }
+    }
+
+    /// Every diagonal running down and to the right, starting from the bottom-left one.
+    pub fn diagonals(&self) -> impl Iterator<Item = Ray<'_, T>> {
+        let left = (0..self.rows).rev().map(|row| (row, 0));
+        let top = (1..self.cols).map(|col| (0, col));
+
+        left.chain(top).map(|start| self.ray(start, (1, 1)))
+    }
+
+    /// Every diagonal running down and to the left, starting from the top-left one.
+    pub fn anti_diagonals(&self) -> impl Iterator<Item = Ray<'_, T>> {
+        let top = (0..self.cols).map(|col| (0, col));
+        let right = (1..self.rows).map(|row| (row, self.cols.saturating_sub(1)));
+
+        top.chain(right).map(|start| self.ray(start, (1, -1)))
+    }
+
+    /// Every position in row-major order.
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let cols = self.cols;
+        (0..self.rows).flat_map(move |row| (0..cols).map(move |col| (row, col)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.positions().zip(&self.cells)
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid::new(self.rows, self.cols, self.cells.iter().map(f).collect())
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: (usize, usize)) -> &T {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("{:?} is outside a {}x{} grid", pos, self.rows, self.cols))
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, pos: (usize, usize)) -> &mut T {
+        let (rows, cols) = (self.rows, self.cols);
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("{:?} is outside a {}x{} grid", pos, rows, cols))
+    }
+}
+
+/// Rows separated by newlines, with each cell's `Display` output written side by side.
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, row) in self.iter_rows().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Iterator over the cells in a straight line through a `Grid`, see `Grid::ray`.
+pub struct Ray<'a, T> {
+    grid: &'a Grid<T>,
+    pos: Option<(usize, usize)>,
+    step: (isize, isize),
+}
+
+impl<'a, T> Iterator for Ray<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let pos = self.pos?;
+        self.pos = self.grid.offset(pos, self.step);
+        Some(&self.grid[pos])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn letters(rows: usize, cols: usize) -> Grid<char> {
+        let cells = (0..rows * cols).map(|i| (b'a' + i as u8) as char).collect();
+        Grid::new(rows, cols, cells)
+    }
+
+    fn collect<'a>(cells: impl Iterator<Item = &'a char>) -> String {
+        cells.collect()
+    }
+
+    #[test]
+    fn test_index_is_row_major() {
+        let grid = letters(2, 3);
+        assert_eq!(grid[(0, 2)], 'c');
+        assert_eq!(grid[(1, 0)], 'd');
+        assert_eq!(grid.get((2, 0)), None);
+        assert_eq!(grid.get((0, 3)), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_new_checks_len() {
+        Grid::new(2, 2, vec![0; 3]);
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let grid = letters(3, 3);
+        assert_eq!(grid.neighbor((1, 1), (-1, -1)), Some(&'a'));
+        assert_eq!(grid.neighbor((1, 1), (1, 1)), Some(&'i'));
+        assert_eq!(grid.neighbor((0, 0), (-1, 0)), None);
+        assert_eq!(grid.neighbor((0, 0), (0, -1)), None);
+        assert_eq!(grid.neighbor((2, 2), (0, 1)), None);
+        assert_eq!(grid.offset((0, 2), (2, -2)), Some((2, 0)));
+        assert_eq!(grid.offset((0, 0), (isize::MIN, 0)), None);
+        assert_eq!(grid.offset((usize::MAX, 0), (1, 0)), None);
+    }
+
+    #[test]
+    fn test_rows_and_columns() {
+        let grid = letters(2, 3);
+        assert_eq!(grid.row(1), &['d', 'e', 'f']);
+        assert_eq!(grid.iter_rows().count(), 2);
+        assert_eq!(collect(grid.column(0)), "ad");
+        assert_eq!(collect(grid.column(2)), "cf");
+    }
+
+    #[test]
+    fn test_diagonals() {
+        // abc
+        // def
+        let grid = letters(2, 3);
+
+        let diagonals = grid.diagonals().map(collect).collect::<Vec<_>>();
+        assert_eq!(diagonals, ["d", "ae", "bf", "c"]);
+
+        let anti_diagonals = grid.anti_diagonals().map(collect).collect::<Vec<_>>();
+        assert_eq!(anti_diagonals, ["a", "bd", "ce", "f"]);
+
+        assert_eq!(collect(grid.ray((1, 2), (0, -1))), "fed");
+        assert_eq!(collect(grid.ray((5, 5), (0, -1))), "");
+    }
+
+    #[test]
+    fn test_diagonals_cover_every_cell_once() {
+        for (rows, cols) in [(1, 1), (1, 5), (5, 1), (4, 7), (7, 4)] {
+            let grid = letters(rows, cols);
+            for lines in [
+                grid.diagonals().map(collect).collect::<Vec<_>>(),
+                grid.anti_diagonals().map(collect).collect::<Vec<_>>(),
+            ] {
+                let mut cells = lines.concat().chars().collect::<Vec<_>>();
+                cells.sort_unstable();
+                assert_eq!(
+                    cells.into_iter().collect::<String>(),
+                    grid.to_string().replace('\n', "")
+                );
+                assert_eq!(lines.len(), rows + cols - 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(letters(2, 3).to_string(), "abc\ndef");
+        assert_eq!(Grid::filled(2, 2, 0).to_string(), "00\n00");
+        assert_eq!(Grid::<u8>::new(0, 0, Vec::new()).to_string(), "");
+    }
+
+    #[test]
+    fn test_iter_and_map() {
+        let mut grid = letters(2, 2).map(|c| c.to_ascii_uppercase());
+        grid[(1, 1)] = '#';
+
+        let cells = grid.iter().collect::<Vec<_>>();
+        assert_eq!(cells[1], ((0, 1), &'B'));
+        assert_eq!(cells[3], ((1, 1), &'#'));
+    }
+}
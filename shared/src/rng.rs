@@ -0,0 +1,42 @@
+/// Deterministic xorshift generator, for generating test and benchmark inputs.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // xorshift gets stuck on zero, which the seed that cancels the constant would give
+        match seed ^ 0x9E37_79B9_7F4A_7C15 {
+            0 => Self(0x9E37_79B9_7F4A_7C15),
+            state => Self(state),
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_no_seed_gets_stuck() {
+        for seed in [0, 1, 0x9E37_79B9_7F4A_7C15, u64::MAX] {
+            let mut rng = Rng::new(seed);
+            assert!((0..100).all(|_| rng.next_u64() != 0));
+        }
+    }
+}